@@ -0,0 +1,301 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// Integer types which solvers can be written against: fixed width ones
+/// report overflow as `None`, while `BigInt` never overflows.
+pub trait Integer: Clone + PartialEq + From<u32> {
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+    /// Division by a small positive number, rounding towards zero.
+    fn div_small(&self, divisor: u32) -> Self;
+
+    fn zero() -> Self {
+        Self::from(0)
+    }
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(impl Integer for $t {
+            fn checked_add(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *rhs)
+            }
+
+            fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_sub(*self, *rhs)
+            }
+
+            fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *rhs)
+            }
+
+            fn div_small(&self, divisor: u32) -> Self {
+                *self / <$t>::from(divisor)
+            }
+        })*
+    };
+}
+
+impl_integer!(u64, i64, u128, i128);
+
+/// An arbitrary precision signed integer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    /// Base 2^32 digits, least significant first, with no trailing zeros.
+    digits: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        // There is no negative zero
+        let negative = negative && !digits.is_empty();
+        Self { negative, digits }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match (self.negative, &self.digits[..]) {
+            (true, _) => None,
+            (false, []) => Some(0),
+            (false, [low]) => Some(*low as u64),
+            (false, [low, high]) => Some((*high as u64) << 32 | *low as u64),
+            _ => None,
+        }
+    }
+
+    fn add_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut digits = Vec::with_capacity(a.len().max(b.len()) + 1);
+        let mut carry = 0;
+        for i in 0..a.len().max(b.len()) {
+            let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+            digits.push(sum as u32);
+            carry = sum >> 32;
+        }
+        digits.push(carry as u32);
+        digits
+    }
+
+    /// `a - b` where `a` is at least as large as `b`.
+    fn sub_magnitudes(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut digits = Vec::with_capacity(a.len());
+        let mut borrow = 0;
+        for (i, &digit) in a.iter().enumerate() {
+            let diff = digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+            digits.push(diff.rem_euclid(1 << 32) as u32);
+            borrow = (diff < 0) as i64;
+        }
+        digits
+    }
+
+    fn cmp_magnitudes(a: &[u32], b: &[u32]) -> Ordering {
+        a.len()
+            .cmp(&b.len())
+            .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+    }
+
+    /// Divide the magnitude in place, returning the remainder.
+    fn div_rem_small(digits: &mut [u32], divisor: u32) -> u32 {
+        let mut rem = 0u64;
+        for digit in digits.iter_mut().rev() {
+            let acc = rem << 32 | *digit as u64;
+            *digit = (acc / divisor as u64) as u32;
+            rem = acc % divisor as u64;
+        }
+        rem as u32
+    }
+}
+
+impl From<u32> for BigInt {
+    fn from(value: u32) -> Self {
+        Self::from(value as u64)
+    }
+}
+
+impl From<u64> for BigInt {
+    fn from(value: u64) -> Self {
+        Self::new(false, vec![value as u32, (value >> 32) as u32])
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let magnitude = Self::from(value.unsigned_abs());
+        Self::new(value < 0, magnitude.digits)
+    }
+}
+
+impl Neg for BigInt {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self::new(!self.negative, self.digits)
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+    fn add(self, rhs: Self) -> Self::Output {
+        if self.negative == rhs.negative {
+            return BigInt::new(
+                self.negative,
+                BigInt::add_magnitudes(&self.digits, &rhs.digits),
+            );
+        }
+
+        // Opposite signs: the larger magnitude decides the sign
+        match BigInt::cmp_magnitudes(&self.digits, &rhs.digits) {
+            Ordering::Less => BigInt::new(
+                rhs.negative,
+                BigInt::sub_magnitudes(&rhs.digits, &self.digits),
+            ),
+            _ => BigInt::new(
+                self.negative,
+                BigInt::sub_magnitudes(&self.digits, &rhs.digits),
+            ),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + &-rhs.clone()
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut digits = vec![0u32; self.digits.len() + rhs.digits.len()];
+        for (i, &a) in self.digits.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in rhs.digits.iter().enumerate() {
+                let acc = digits[i + j] as u64 + a as u64 * b as u64 + carry;
+                digits[i + j] = acc as u32;
+                carry = acc >> 32;
+            }
+            digits[i + rhs.digits.len()] = carry as u32;
+        }
+        BigInt::new(self.negative != rhs.negative, digits)
+    }
+}
+
+impl Integer for BigInt {
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        Some(self - rhs)
+    }
+
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        Some(self * rhs)
+    }
+
+    fn div_small(&self, divisor: u32) -> Self {
+        let mut digits = self.digits.clone();
+        Self::div_rem_small(&mut digits, divisor);
+        BigInt::new(self.negative, digits)
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => Self::cmp_magnitudes(&self.digits, &other.digits),
+            (true, true) => Self::cmp_magnitudes(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const CHUNK: u32 = 1_000_000_000;
+
+        // Peel off nine decimal digits at a time
+        let mut digits = self.digits.clone();
+        let mut chunks = Vec::new();
+        while !digits.is_empty() {
+            chunks.push(Self::div_rem_small(&mut digits, CHUNK));
+            while digits.last() == Some(&0) {
+                digits.pop();
+            }
+        }
+
+        let mut text = String::from(if self.negative { "-" } else { "" });
+        match chunks.split_last() {
+            None => text.push('0'),
+            Some((first, rest)) => {
+                text.push_str(&first.to_string());
+                for chunk in rest.iter().rev() {
+                    text.push_str(&format!("{chunk:09}"));
+                }
+            }
+        }
+        f.pad(&text)
+    }
+}
+
+#[test]
+fn test_against_i128() {
+    let values: Vec<i64> = vec![
+        0,
+        1,
+        -1,
+        42,
+        -999_999_999,
+        1_000_000_000,
+        u32::MAX as i64,
+        -(u32::MAX as i64) - 1,
+        i64::MAX,
+        i64::MIN,
+    ];
+
+    for &a in &values {
+        let big_a = BigInt::from(a);
+        assert_eq!(big_a.to_string(), a.to_string());
+        assert_eq!(big_a.to_u64(), u64::try_from(a).ok());
+
+        for &b in &values {
+            let big_b = BigInt::from(b);
+            let (a, b) = (a as i128, b as i128);
+            assert_eq!((&big_a + &big_b).to_string(), (a + b).to_string());
+            assert_eq!((&big_a - &big_b).to_string(), (a - b).to_string());
+            assert_eq!((&big_a * &big_b).to_string(), (a * b).to_string());
+            assert_eq!(big_a.cmp(&big_b), a.cmp(&b));
+        }
+        assert_eq!(big_a.div_small(7).to_string(), (a / 7).to_string());
+    }
+}
+
+#[test]
+fn test_beyond_u64() {
+    let mut factorial = BigInt::from(1u32);
+    for n in 1..=30u32 {
+        factorial = &factorial * &BigInt::from(n);
+    }
+    assert_eq!(factorial.to_string(), "265252859812191058636308480000000");
+    assert_eq!(factorial.to_u64(), None);
+    assert_eq!(Integer::checked_mul(&u64::MAX, &2), None);
+}
@@ -1,8 +1,21 @@
 use std::path::Path;
 
-pub trait DailyChallenge {
+pub trait DailyChallenge: Sync {
     fn part1(&self, file: &Path) -> u64;
     fn part2(&self, file: &Path) -> u64;
+
+    fn title(&self) -> &'static str;
+    /// One sentence summary of the puzzle
+    fn description(&self) -> &'static str;
+
+    /// A text rendering of the solved puzzle, if the day has one
+    fn visualize(&self, _file: &Path) -> Option<String> {
+        None
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
 }
 
 pub trait Solver {
@@ -0,0 +1,169 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// States from `offset` onwards repeat every `length` steps.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Cycle {
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// The earliest step which reaches the same state as step `n`.
+    pub fn equivalent_step(&self, n: usize) -> usize {
+        if n < self.offset {
+            n
+        } else {
+            self.offset + (n - self.offset) % self.length
+        }
+    }
+}
+
+/// Brent's cycle detection. Only a few states are kept at a time, but the
+/// state machine must eventually repeat or this never returns.
+#[cfg(test)]
+pub fn brent<S, F>(start: &S, step: F) -> Cycle
+where
+    S: Clone + PartialEq,
+    F: Fn(&S) -> S,
+{
+    // Find the cycle length by racing a hare against a tortoise which
+    // teleports to the hare at every power of two
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(start);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+
+    // Find the offset with two pointers `length` steps apart
+    let mut tortoise = start.clone();
+    let mut hare = start.clone();
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+    let mut offset = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        offset += 1;
+    }
+
+    Cycle { offset, length }
+}
+
+/// Apply `step` to `start` `n` times, skipping whole laps of `cycle`.
+#[cfg(test)]
+pub fn state_after<S, F>(start: &S, step: F, cycle: Cycle, n: usize) -> S
+where
+    S: Clone,
+    F: Fn(&S) -> S,
+{
+    let mut state = start.clone();
+    for _ in 0..cycle.equivalent_step(n) {
+        state = step(&state);
+    }
+    state
+}
+
+fn hash_of<S: Hash>(state: &S) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Every state visited from a start, looked up by hash and confirmed by
+/// equality so hash collisions cannot produce a false cycle.
+pub struct StateHistory<S> {
+    states: Vec<S>,
+    cycle: Option<Cycle>,
+}
+
+impl<S: Hash + Eq> StateHistory<S> {
+    /// Step from `start` until a state repeats or `max_steps` steps are taken.
+    pub fn explore<F>(start: S, max_steps: usize, mut step: F) -> Self
+    where
+        F: FnMut(&S) -> S,
+    {
+        let mut by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut states = vec![start];
+        by_hash.entry(hash_of(&states[0])).or_default().push(0);
+
+        let mut cycle = None;
+        for steps in 1..=max_steps {
+            let next = step(&states[steps - 1]);
+            let candidates = by_hash.entry(hash_of(&next)).or_default();
+
+            if let Some(&first) = candidates.iter().find(|&&i| states[i] == next) {
+                cycle = Some(Cycle {
+                    offset: first,
+                    length: steps - first,
+                });
+                break;
+            }
+
+            candidates.push(steps);
+            states.push(next);
+        }
+
+        Self { states, cycle }
+    }
+
+    pub fn cycle(&self) -> Option<Cycle> {
+        self.cycle
+    }
+
+    /// Every distinct state in the order it was first reached.
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    /// The state after `n` steps, or `None` if that is past the explored
+    /// states and no cycle was found.
+    pub fn state_after(&self, n: usize) -> Option<&S> {
+        match self.cycle {
+            Some(cycle) => self.states.get(cycle.equivalent_step(n)),
+            None => self.states.get(n),
+        }
+    }
+}
+
+#[test]
+fn test_brent_matches_history() {
+    let step = |x: &u32| (x * x + 1) % 255;
+    for start in 0..255 {
+        let history = StateHistory::explore(start, 1000, step);
+        assert_eq!(Some(brent(&start, step)), history.cycle(), "start {start}");
+    }
+}
+
+#[test]
+fn test_state_after() {
+    let step = |x: &u32| (x * x + 1) % 255;
+    let start = 3;
+    let history = StateHistory::explore(start, 1000, step);
+    let cycle = history.cycle().unwrap();
+
+    let mut expected = start;
+    for n in 0..100 {
+        assert_eq!(history.state_after(n), Some(&expected));
+        assert_eq!(state_after(&start, step, cycle, n), expected);
+        expected = step(&expected);
+    }
+}
+
+#[test]
+fn test_history_limit() {
+    let history = StateHistory::explore(0u64, 10, |x| x + 1);
+    assert_eq!(history.cycle(), None);
+    assert_eq!(history.state_after(10), Some(&10));
+    assert_eq!(history.state_after(11), None);
+}
@@ -1,8 +1,12 @@
 use crate::challenge::DailyChallenge;
+use crate::graph::{self, Graph};
+use crate::parse::{self, ParseError};
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::fs;
 use std::ops::Neg;
 use std::path::Path;
+use std::str::FromStr;
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 enum Direction {
@@ -98,7 +102,7 @@ impl Tile {
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 struct Coord {
     row: i32,
     col: i32,
@@ -137,11 +141,21 @@ struct Map {
     tiles: Vec<Vec<Tile>>,
 }
 
-struct LoopPath(Vec<Coord>);
+/// The loop's tiles in the order they are walked, along with a set of them
+/// for membership checks.
+struct LoopPath {
+    tiles: Vec<Coord>,
+    members: HashSet<Coord>,
+}
 
 impl LoopPath {
+    fn new(tiles: Vec<Coord>) -> Self {
+        let members = tiles.iter().copied().collect();
+        Self { tiles, members }
+    }
+
     fn contains(&self, coord: Coord) -> bool {
-        self.0.contains(&coord)
+        self.members.contains(&coord)
     }
 }
 
@@ -149,23 +163,19 @@ impl Map {
     fn from_file(file: &Path) -> Result<Self, String> {
         let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
 
-        let mut tiles: Vec<Vec<Tile>> = Vec::new();
-        let mut start: Option<Coord> = None;
+        let tiles = parse::grid(&contents, Tile::try_from).map_err(|e| e.to_string())?;
 
-        for (rn, line) in contents.lines().enumerate() {
-            let mut row: Vec<Tile> = Vec::new();
-
-            for (cn, c) in line.chars().enumerate() {
-                let tile = Tile::try_from(c)?;
+        let mut start: Option<Coord> = None;
+        for (rn, row) in tiles.iter().enumerate() {
+            for (cn, &tile) in row.iter().enumerate() {
                 if tile == Tile::Start {
                     if start.is_some() {
-                        return Err("Multiple start tiles".to_string());
+                        let err = ParseError::new(rn + 1, cn + 1, "Multiple start tiles");
+                        return Err(err.to_string());
                     }
                     start = Some(Coord::new(rn as i32, cn as i32));
                 }
-                row.push(tile);
             }
-            tiles.push(row);
         }
 
         let start = start.ok_or("Missing start tile".to_string())?;
@@ -186,31 +196,26 @@ impl Map {
     }
 
     fn find_loop(&self) -> Option<LoopPath> {
-        for start_direction in ALL_DIRECTIONS {
-            let mut loop_tiles = vec![self.start];
-            let mut move_direction = start_direction;
-
-            loop {
-                let prev_coord = *(loop_tiles.last().unwrap());
-                let current_coord = prev_coord.go(move_direction);
-                let Some(current_tile) = self.at(current_coord) else {
-                    break;
-                };
+        // Pipes only connect to the start and one other pipe, so a
+        // depth-first walk follows the loop in order
+        let loop_tiles = graph::dfs(self, self.start);
+        let last = *loop_tiles.last()?;
+        let closed = loop_tiles.len() > 2 && self.neighbours(&last).contains(&self.start);
 
-                if current_tile == Tile::Start {
-                    return Some(LoopPath(loop_tiles));
-                }
+        closed.then(|| LoopPath::new(loop_tiles))
+    }
 
-                if let Some(exit_dir) = current_tile.enter_from(-move_direction) {
-                    move_direction = exit_dir;
-                    loop_tiles.push(current_coord);
-                } else {
-                    break;
-                }
-            }
-        }
+    /// The directions a tile connects to, with the start tile inferred
+    fn connections(&self, coord: Coord) -> Option<(Direction, Direction)> {
+        let tile = match self.at(coord)? {
+            Tile::Start => self.infer_start_tile()?,
+            tile => tile,
+        };
 
-        None
+        match tile {
+            Tile::Pipe(a, b) => Some((a, b)),
+            _ => None,
+        }
     }
 
     fn infer_start_tile(&self) -> Option<Tile> {
@@ -234,8 +239,8 @@ impl Map {
         }
     }
 
-    #[allow(dead_code)]
-    fn print_masked(&self, mask: impl Fn(Coord) -> bool) {
+    fn render_masked(&self, mask: impl Fn(Coord) -> bool) -> String {
+        let mut rendered = String::new();
         for (row, row_tiles) in self.tiles.iter().enumerate() {
             for (col, tile) in row_tiles.iter().enumerate() {
                 let coord = Coord::new(row as i32, col as i32);
@@ -244,11 +249,31 @@ impl Map {
                 } else {
                     '_'
                 };
-                print!("{c}");
+                rendered.push(c);
             }
-            print!("\n");
+            rendered.push('\n');
         }
-        print!("\n");
+        rendered
+    }
+}
+
+impl Graph for Map {
+    type Node = Coord;
+
+    /// Pipes which connect to each other in both directions
+    fn edges(&self, coord: &Coord) -> Vec<(Coord, u64)> {
+        let Some((a, b)) = self.connections(*coord) else {
+            return Vec::new();
+        };
+
+        [a, b]
+            .into_iter()
+            .filter_map(|direction| {
+                let next = coord.go(direction);
+                let (x, y) = self.connections(next)?;
+                (x == -direction || y == -direction).then_some((next, 1))
+            })
+            .collect()
     }
 }
 
@@ -308,14 +333,86 @@ fn find_enlosed_tiles(map: &Map, loop_path: &LoopPath) -> Vec<Coord> {
     enclosed
 }
 
+/// Counts enclosed tiles from the loop alone. The shoelace formula gives the
+/// area of the polygon through the loop tiles' centres, then Pick's theorem
+/// `A = I + B/2 - 1` gives the number `I` of tiles strictly inside.
+fn count_enclosed_by_area(loop_path: &LoopPath) -> u64 {
+    let tiles = &loop_path.tiles;
+    let twice_area: i64 = tiles
+        .iter()
+        .zip(tiles.iter().cycle().skip(1))
+        .map(|(a, b)| a.row as i64 * b.col as i64 - b.row as i64 * a.col as i64)
+        .sum::<i64>()
+        .abs();
+    let boundary = tiles.len() as i64;
+
+    u64::try_from((twice_area - boundary) / 2 + 1).expect("Loop encloses a negative area")
+}
+
+/// How part 2 counts the tiles enclosed by the loop
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AreaEngine {
+    /// Scan each row, counting how many times the loop is crossed
+    Scanline,
+    /// Shoelace formula and Pick's theorem over the loop's vertices
+    #[default]
+    Shoelace,
+}
+
+impl AreaEngine {
+    fn name(&self) -> &'static str {
+        match self {
+            AreaEngine::Scanline => "scanline",
+            AreaEngine::Shoelace => "shoelace",
+        }
+    }
+
+    fn count_enclosed(&self, map: &Map, loop_path: &LoopPath) -> u64 {
+        match self {
+            AreaEngine::Scanline => find_enlosed_tiles(map, loop_path).len() as u64,
+            AreaEngine::Shoelace => count_enclosed_by_area(loop_path),
+        }
+    }
+}
+
+impl FromStr for AreaEngine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "scanline" => Ok(AreaEngine::Scanline),
+            "shoelace" => Ok(AreaEngine::Shoelace),
+            _ => Err(format!(
+                "Unknown engine '{s}', expected scanline or shoelace"
+            )),
+        }
+    }
+}
+
 #[derive(Default)]
-pub struct Day10;
+pub struct Day10 {
+    engine: AreaEngine,
+}
+
+impl Day10 {
+    pub fn new(engine: AreaEngine) -> Self {
+        Self { engine }
+    }
+}
 
 impl DailyChallenge for Day10 {
+    fn title(&self) -> &'static str {
+        "Pipe Maze"
+    }
+
+    fn description(&self) -> &'static str {
+        "Find the loop of pipes through the start tile and count the tiles it encloses."
+    }
+
     fn part1(&self, file: &Path) -> u64 {
         let map = Map::from_file(file).unwrap();
-        if let Some(LoopPath(map_loop)) = map.find_loop() {
-            return map_loop.len() as u64 / 2;
+        if let Some(map_loop) = map.find_loop() {
+            return map_loop.tiles.len() as u64 / 2;
         }
 
         panic!("No loop found");
@@ -324,11 +421,21 @@ impl DailyChallenge for Day10 {
     fn part2(&self, file: &Path) -> u64 {
         let map = Map::from_file(file).unwrap();
         if let Some(map_loop) = map.find_loop() {
-            let enclosed = find_enlosed_tiles(&map, &map_loop);
-            return enclosed.len() as u64;
+            return self.engine.count_enclosed(&map, &map_loop);
         }
         panic!("No loop found");
     }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![("part2_engine", self.engine.name().to_string())]
+    }
+
+    /// The map with every tile off the loop blanked out
+    fn visualize(&self, file: &Path) -> Option<String> {
+        let map = Map::from_file(file).ok()?;
+        let on_loop = map.find_loop()?;
+        Some(map.render_masked(|coord| on_loop.contains(coord)))
+    }
 }
 
 #[test]
@@ -384,15 +491,28 @@ fn test_from_file() {
 
 #[test]
 fn test_part1() {
-    assert_eq!(Day10.part1(Path::new("data/10.sample")), 8)
+    assert_eq!(Day10::default().part1(Path::new("data/10.sample")), 8)
 }
 
 #[test]
 fn test_part2() {
-    assert_eq!(Day10.part2(Path::new("data/10.sample2")), 4)
+    assert_eq!(Day10::default().part2(Path::new("data/10.sample2")), 4)
 }
 
 #[test]
 fn test_part2_larger() {
-    assert_eq!(Day10.part2(Path::new("data/10.sample3")), 10)
+    assert_eq!(Day10::default().part2(Path::new("data/10.sample3")), 10)
+}
+
+#[test]
+fn test_engines_agree() {
+    for (sample, expected) in [("10.sample", 1), ("10.sample2", 4), ("10.sample3", 10)] {
+        let counts: Vec<u64> = [AreaEngine::Scanline, AreaEngine::Shoelace]
+            .into_iter()
+            .map(|engine| Day10::new(engine).part2(&Path::new("data").join(sample)))
+            .collect();
+        assert_eq!(counts, vec![expected, expected], "{sample}");
+    }
+    assert_eq!("scanline".parse(), Ok(AreaEngine::Scanline));
+    assert!("pixel".parse::<AreaEngine>().is_err());
 }
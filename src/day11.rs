@@ -1,4 +1,5 @@
 use crate::challenge::DailyChallenge;
+use crate::parse;
 use std::fs;
 use std::path::Path;
 
@@ -114,17 +115,7 @@ impl Universe {
     fn from_file(file: &Path) -> Result<Self, String> {
         let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
 
-        let mut pixels: Vec<Vec<Pixel>> = Vec::new();
-
-        for line in contents.lines() {
-            let mut row: Vec<Pixel> = Vec::new();
-
-            for c in line.chars() {
-                let tile = Pixel::try_from(c)?;
-                row.push(tile);
-            }
-            pixels.push(row);
-        }
+        let pixels = parse::grid(&contents, Pixel::try_from).map_err(|e| e.to_string())?;
 
         Ok(Universe::new(&pixels))
     }
@@ -159,18 +150,36 @@ fn galaxy_distance_sum(universe: &Universe, expand_factor: usize) -> u64 {
     distance_sum
 }
 
+const PART1_EXPAND_FACTOR: usize = 2;
+const PART2_EXPAND_FACTOR: usize = 1000000;
+
 #[derive(Default)]
 pub struct Day11;
 
 impl DailyChallenge for Day11 {
+    fn title(&self) -> &'static str {
+        "Cosmic Expansion"
+    }
+
+    fn description(&self) -> &'static str {
+        "Sum distances between galaxies after empty rows and columns expand."
+    }
+
     fn part1(&self, file: &Path) -> u64 {
         let universe = Universe::from_file(file).unwrap();
-        galaxy_distance_sum(&universe, 2)
+        galaxy_distance_sum(&universe, PART1_EXPAND_FACTOR)
     }
 
     fn part2(&self, file: &Path) -> u64 {
         let universe = Universe::from_file(file).unwrap();
-        galaxy_distance_sum(&universe, 1000000)
+        galaxy_distance_sum(&universe, PART2_EXPAND_FACTOR)
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("part1_expand_factor", PART1_EXPAND_FACTOR.to_string()),
+            ("part2_expand_factor", PART2_EXPAND_FACTOR.to_string()),
+        ]
     }
 }
 
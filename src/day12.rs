@@ -1,5 +1,9 @@
+use crate::bigint::{BigInt, Integer};
 use crate::challenge::DailyChallenge;
-use std::collections::HashMap;
+use crate::memo::Memo;
+use crate::parallel::par_map;
+use crate::parse::{self, Cursor, ParseError};
+use crate::progress::Progress;
 use std::fs;
 use std::path::Path;
 
@@ -36,20 +40,10 @@ impl SpringRecord {
         }
     }
 
-    fn from_string(string: &str) -> Result<Self, String> {
-        let mut components = string.split_whitespace();
-
-        let springs = components.next().ok_or("Could not get springs")?;
-        let springs = springs
-            .chars()
-            .map(Spring::try_from)
-            .collect::<Result<Vec<Spring>, String>>()?;
-
-        let damaged_groups = components.next().ok_or("Could not get damaged groups")?;
-        let damaged_groups = damaged_groups
-            .split(",")
-            .map(|n| n.parse::<usize>().map_err(|e| e.to_string()))
-            .collect::<Result<Vec<usize>, String>>()?;
+    fn parse(cursor: &mut Cursor) -> Result<Self, ParseError> {
+        let springs = cursor.cells(Spring::try_from)?;
+        cursor.expect(" ")?;
+        let damaged_groups = cursor.separated(",", Cursor::number)?;
 
         Ok(Self {
             springs,
@@ -57,11 +51,20 @@ impl SpringRecord {
         })
     }
 
+    #[cfg(test)]
+    fn from_string(string: &str) -> Result<Self, String> {
+        let mut cursor = Cursor::new(1, string);
+        let record = Self::parse(&mut cursor).map_err(|e| e.to_string())?;
+        cursor.finish().map_err(|e| e.to_string())?;
+        Ok(record)
+    }
+
     fn vec_from_file(file: &Path) -> Result<Vec<Self>, String> {
         let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
-        contents.lines().map(Self::from_string).collect()
+        parse::lines(&contents, Self::parse).map_err(|e| e.to_string())
     }
 
+    #[cfg(test)]
     fn is_valid(&self, springs: &Vec<Spring>) -> bool {
         let mut group_size = 0;
         let mut expected_iter = self.damaged_groups.iter();
@@ -113,128 +116,165 @@ impl SpringRecord {
     }
 }
 
-struct CachedSolver {
-    cache: HashMap<(Vec<Spring>, Vec<usize>, usize), usize>,
+/// Position within a record: the springs and groups before these indices
+/// have already been matched
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+struct SolveKey {
+    spring: u32,
+    group: u32,
+    current: u32,
 }
 
-impl CachedSolver {
-    fn new() -> Self {
-        Self {
-            cache: HashMap::new(),
-        }
-    }
+type ArrangementMemo<T> = Memo<SolveKey, Option<T>>;
 
-    fn solve_record(&mut self, record: &SpringRecord) -> usize {
-        self.solve(&record.springs, &record.damaged_groups, 0)
-    }
+/// Counts arrangements in `T`, where `None` means the count overflowed.
+/// Keys are indices into `record`, so each record gets a fresh cache.
+fn count_arrangements<T: Integer>(record: &SpringRecord) -> Option<T> {
+    solve(&mut Memo::new(), record, 0, 0, 0)
+}
 
-    fn solve(&mut self, springs: &[Spring], groups: &[usize], current: usize) -> usize {
-        let key = (springs.to_vec(), groups.to_vec(), current);
-        if let Some(&result) = self.cache.get(&key) {
-            return result;
-        };
+fn solve<T: Integer>(
+    memo: &mut ArrangementMemo<T>,
+    record: &SpringRecord,
+    spring: usize,
+    group: usize,
+    current: usize,
+) -> Option<T> {
+    let key = SolveKey {
+        spring: spring as u32,
+        group: group as u32,
+        current: current as u32,
+    };
+    memo.get_or_compute(record, key, solve_uncached)
+}
 
-        let result = self.solve_uncached(springs, groups, current);
-        self.cache.insert(key, result);
+fn solve_uncached<T: Integer>(
+    memo: &mut ArrangementMemo<T>,
+    record: &SpringRecord,
+    key: &SolveKey,
+) -> Option<T> {
+    // springs: sequence of springs yet to consider
+    // groups: damaged spring group sizes yet to find
+    // current: number of springs in current damaged group
+    let (next, group) = (key.spring as usize + 1, key.group as usize);
+    let springs = &record.springs[key.spring as usize..];
+    let groups = &record.damaged_groups[group..];
+    let current = key.current as usize;
+
+    let Some(spring) = springs.first() else {
+        // No springs left - have we matched all damaged groups?
+        let [group_size, other_groups @ ..] = &groups[..] else {
+            // no groups left
+            // valid if we are not in a damaged group
+            return Some(T::from((current == 0) as u32));
+        };
 
-        result
-    }
+        if !other_groups.is_empty() {
+            // multiple groups need matching but we've run out of springs
+            return Some(T::zero());
+        }
 
-    fn solve_uncached(&mut self, springs: &[Spring], groups: &[usize], current: usize) -> usize {
-        // springs: sequence of springs yet to consider
-        // groups: damaged spring group sizes yet to find
-        // current: number of springs in current damaged group
-
-        let Some(spring) = springs.first() else {
-            // No springs left - have we matched all damaged groups?
-            let [group_size, other_groups @ ..] = &groups[..] else {
-                // no groups left
-                // valid if we are not in a damaged group
-                return (current == 0) as usize;
-            };
-
-            if !other_groups.is_empty() {
-                // multiple groups need matching but we've run out of springs
-                return 0;
-            }
+        return Some(T::from((current == *group_size) as u32));
+    };
 
-            return (current == *group_size) as usize;
-        };
+    let Some(&group_size) = groups.first() else {
+        // No groups left to find
+        if current > 0 {
+            return Some(T::zero());
+        } else if springs.contains(&Spring::Damaged) {
+            // Matched all groups but there are more damaged springs which is impossible
+            return Some(T::zero());
+        } else {
+            // Any unknown springs are fine - a single combination
+            return Some(T::from(1));
+        }
+    };
 
-        let Some(&group_size) = groups.first() else {
-            // No groups left to find
-            if current > 0 {
-                return 0;
-            } else if springs.contains(&Spring::Damaged) {
-                // Matched all groups but there are more damaged springs which is impossible
-                return 0;
+    match spring {
+        Spring::Fine => {
+            if current == 0 {
+                return solve(memo, record, next, group, 0);
             } else {
-                // Any unknown springs are fine - a single combination
-                return 1;
-            }
-        };
-
-        let rest = &springs[1..];
-
-        match spring {
-            Spring::Fine => {
-                if current == 0 {
-                    return self.solve(rest, groups, 0);
+                // Finished a damaged spring group
+                if current == group_size {
+                    return solve(memo, record, next, group + 1, 0);
                 } else {
-                    // Finished a damaged spring group
-                    if current == group_size {
-                        return self.solve(rest, &groups[1..], 0);
-                    } else {
-                        // Found a damaged group which is not the right size
-                        return 0;
-                    }
+                    // Found a damaged group which is not the right size
+                    return Some(T::zero());
                 }
             }
-            Spring::Damaged => {
-                if current >= group_size {
-                    // This group is bigger than the expected size
-                    return 0;
-                } else {
-                    return self.solve(rest, groups, current + 1);
-                }
+        }
+        Spring::Damaged => {
+            if current >= group_size {
+                // This group is bigger than the expected size
+                return Some(T::zero());
+            } else {
+                return solve(memo, record, next, group, current + 1);
             }
-            Spring::Unknown => {
-                if current == 0 {
-                    return self.solve(rest, groups, 1)  // if this is damaged
-                        + self.solve(rest, groups, 0); // if this is fine
-                } else if current == group_size {
-                    // Finished the group of damaged springs - move to next one.
-                    // This spring is fine.
-                    return self.solve(rest, &groups[1..], 0);
-                } else {
-                    // In the middle of a group - must be damaged
-                    return self.solve(rest, groups, current + 1);
-                }
+        }
+        Spring::Unknown => {
+            if current == 0 {
+                let damaged = solve(memo, record, next, group, 1)?;
+                let fine = solve(memo, record, next, group, 0)?;
+                return damaged.checked_add(&fine);
+            } else if current == group_size {
+                // Finished the group of damaged springs - move to next one.
+                // This spring is fine.
+                return solve(memo, record, next, group + 1, 0);
+            } else {
+                // In the middle of a group - must be damaged
+                return solve(memo, record, next, group, current + 1);
             }
         }
     }
 }
 
+const UNFOLD_FACTOR: usize = 5;
+
 #[derive(Default)]
 pub struct Day12;
 
+fn total_arrangements<T: Integer + Send>(records: &[SpringRecord], unfold: usize) -> Option<T> {
+    let progress = Progress::new("Day 12 records", Some(records.len() as u64));
+    par_map(records, |r| {
+        let arrangements = count_arrangements(&r.unfold(unfold));
+        progress.inc();
+        arrangements
+    })
+    .into_iter()
+    .try_fold(T::zero(), |total, n| total.checked_add(&n?))
+}
+
+/// Count in `u64`, and only if that overflows recount exactly to report
+/// the true answer
+fn checked_total(records: &[SpringRecord], unfold: usize) -> u64 {
+    total_arrangements::<u64>(records, unfold).unwrap_or_else(|| {
+        let total = total_arrangements::<BigInt>(records, unfold).expect("BigInt cannot overflow");
+        panic!("Arrangement count {total} does not fit in u64")
+    })
+}
+
 impl DailyChallenge for Day12 {
+    fn title(&self) -> &'static str {
+        "Hot Springs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Count the arrangements of damaged springs matching each record, then unfolded records."
+    }
+
     fn part1(&self, file: &Path) -> u64 {
         let records = SpringRecord::vec_from_file(file).unwrap();
-        let mut solver = CachedSolver::new();
-        records
-            .iter()
-            .map(|r| solver.solve_record(r))
-            .sum::<usize>() as u64
+        checked_total(&records, 1)
     }
 
     fn part2(&self, file: &Path) -> u64 {
         let records = SpringRecord::vec_from_file(file).unwrap();
-        let mut solver = CachedSolver::new();
-        records
-            .iter()
-            .map(|r| solver.solve_record(&r.unfold(5)))
-            .sum::<usize>() as u64
+        checked_total(&records, UNFOLD_FACTOR)
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![("part2_unfold_factor", UNFOLD_FACTOR.to_string())]
     }
 }
 
@@ -265,7 +305,34 @@ fn test_unfolded_permuations() {
     let rec = SpringRecord::from_string(".??..??...?##. 1,1,3")
         .unwrap()
         .unfold(5);
-    assert_eq!(CachedSolver::new().solve_record(&rec), 16384);
+    assert_eq!(count_arrangements(&rec), Some(16384u64));
+}
+
+#[test]
+fn test_cache_stats() {
+    let rec = SpringRecord::from_string("?###???????? 3,2,1").unwrap();
+    let mut memo = Memo::new();
+    assert_eq!(solve(&mut memo, &rec.unfold(5), 0, 0, 0), Some(506250u64));
+
+    let stats = memo.stats();
+    assert!(stats.hits > 0);
+    assert!(stats.entries <= stats.peak_entries);
+    assert_eq!(stats.peak_bytes, stats.peak_entries * (12 + 16));
+}
+
+#[test]
+fn test_overflow() {
+    // 20 single damaged springs placed among 339 unknown ones
+    let rec = SpringRecord::from_string("???????????????? 1").unwrap();
+    let unfolded = rec.unfold(20);
+    assert_eq!(count_arrangements::<u64>(&unfolded), None);
+
+    let exact = count_arrangements::<BigInt>(&unfolded).unwrap();
+    assert!(exact.to_u64().is_none());
+    assert_eq!(
+        count_arrangements::<u128>(&unfolded).map(|n| n.to_string()),
+        Some(exact.to_string())
+    );
 }
 
 #[test]
@@ -1,4 +1,7 @@
 use crate::challenge::DailyChallenge;
+use crate::parallel::par_map;
+use crate::parse::{self, ParseError};
+use std::fmt;
 use std::ops::Deref;
 use std::path::Path;
 use std::str::FromStr;
@@ -18,9 +21,26 @@ type TerrainGrid = Vec<Vec<Terrain>>;
 #[derive(Debug)]
 enum Error {
     InvalidTerrain,
+    Parse(ParseError),
     IOError(io::Error),
 }
 
+impl From<ParseError> for Error {
+    fn from(value: ParseError) -> Self {
+        Self::Parse(value)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidTerrain => write!(f, "Invalid terrain"),
+            Error::Parse(e) => fmt::Display::fmt(&e, f),
+            Error::IOError(e) => fmt::Display::fmt(&e, f),
+        }
+    }
+}
+
 impl TryFrom<char> for Terrain {
     type Error = Error;
     fn try_from(value: char) -> Result<Self, Self::Error> {
@@ -67,15 +87,7 @@ impl Transpose for Note {
 impl FromStr for Note {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let terrain = s
-            .split_whitespace()
-            .map(|line| {
-                line.chars()
-                    .map(|c| Terrain::try_from(c))
-                    .collect::<Result<Vec<Terrain>, Error>>()
-            })
-            .collect::<Result<TerrainGrid, Error>>()?;
-
+        let terrain = parse::grid(s, Terrain::try_from)?;
         Ok(Self { terrain })
     }
 }
@@ -102,10 +114,14 @@ impl FromStr for Notes {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let notes = s
-            .split("\n\n")
-            .map(|s| s.parse::<Note>())
-            .collect::<Result<Vec<Note>, Error>>()?;
+        let notes = parse::blocks(s)
+            .iter()
+            .map(|block| {
+                block
+                    .grid(Terrain::try_from)
+                    .map(|terrain| Note { terrain })
+            })
+            .collect::<Result<Vec<Note>, ParseError>>()?;
         Ok(Self(notes))
     }
 }
@@ -123,7 +139,7 @@ trait Solver {
     }
 
     fn summarize_notes(notes: &Notes) -> u64 {
-        notes.iter().map(|n| Self::summarize(n) as u64).sum()
+        par_map(notes, |n| Self::summarize(n) as u64).iter().sum()
     }
 }
 
@@ -184,6 +200,14 @@ impl Solver for Part2 {
 }
 
 impl DailyChallenge for Day13 {
+    fn title(&self) -> &'static str {
+        "Point of Incidence"
+    }
+
+    fn description(&self) -> &'static str {
+        "Find lines of reflection in patterns of ash and rocks, then ones with a single smudge."
+    }
+
     fn part1(&self, file: &Path) -> u64 {
         let notes: Notes = fs::read_to_string(file).unwrap().parse().unwrap();
 
@@ -1,22 +1,32 @@
 use crate::challenge::{DailyChallenge, Solver};
-use std::collections::hash_map::DefaultHasher;
+use crate::cycle::StateHistory;
+use crate::parse::{self, ParseError};
+use crate::progress::Progress;
 use std::error::Error;
 use std::fmt::{self, Debug, Write};
-use std::hash::{Hash, Hasher};
 use std::ops::{Deref, DerefMut, Index, IndexMut, Range};
 use std::path::Path;
 use std::str::FromStr;
 use std::{fs, io};
 
+const SPIN_CYCLES: usize = 1_000_000_000;
+
 #[derive(Default)]
 pub struct Day14;
 
 #[derive(Debug)]
 enum Day14Error {
     InvalidRock,
+    Parse(ParseError),
     IOError(io::Error),
 }
 
+impl From<ParseError> for Day14Error {
+    fn from(value: ParseError) -> Self {
+        Self::Parse(value)
+    }
+}
+
 impl From<io::Error> for Day14Error {
     fn from(value: io::Error) -> Self {
         Self::IOError(value)
@@ -30,6 +40,7 @@ impl fmt::Display for Day14Error {
         use Day14Error as E;
         match self {
             E::InvalidRock => write!(f, "Invalid rock"),
+            E::Parse(e) => std::fmt::Display::fmt(&e, f),
             E::IOError(e) => std::fmt::Display::fmt(&e, f),
         }
     }
@@ -43,7 +54,7 @@ enum Direction {
     West,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 enum Rock {
     Round,
     Cube,
@@ -103,7 +114,7 @@ impl Coord {
     }
 }
 
-#[derive(PartialEq, Clone, Hash)]
+#[derive(PartialEq, Eq, Clone, Hash)]
 struct Platform {
     rocks: Vec<Vec<Rock>>,
     size: usize,
@@ -168,13 +179,6 @@ impl Platform {
         load
     }
 
-    fn state_hash(&self) -> u64 {
-        let mut hasher = DefaultHasher::new();
-        self.hash(&mut hasher);
-
-        hasher.finish()
-    }
-
     fn cycle(&mut self) {
         self.tilt(Direction::North);
         self.tilt(Direction::West);
@@ -222,13 +226,8 @@ impl FromIterator<Vec<Rock>> for Platform {
 impl FromStr for Platform {
     type Err = Day14Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.split_whitespace()
-            .map(|line| {
-                line.chars()
-                    .map(|c| Rock::try_from(c))
-                    .collect::<Result<Vec<Rock>, Day14Error>>()
-            })
-            .collect()
+        let rocks = parse::grid(s, Rock::try_from)?;
+        Ok(rocks.into_iter().collect())
     }
 }
 
@@ -267,59 +266,35 @@ impl Part2 {
     }
 }
 
-#[derive(Debug)]
-struct Cycle {
-    offset: usize,
-    length: usize,
-}
-
 impl Solver for Part2 {
     type Input = Platform;
     type Output = u64;
 
     fn solve(&self, item: &Self::Input) -> Self::Output {
-        let mut first_observations: Vec<u64> = Vec::new();
-        let mut platform = Platform::clone(item);
-
-        let mut iteration = 0;
-        let cycle: Option<Cycle> = loop {
-            if iteration == self.iterations {
-                break None;
-            }
-
-            let state_hash = platform.state_hash();
-
-            if let Some(first) = first_observations.iter().position(|&h| h == state_hash) {
-                let cycle = Cycle {
-                    offset: first,
-                    length: iteration - first,
-                };
-                break Some(cycle);
-            };
-
-            first_observations.push(state_hash);
-
-            platform.cycle();
-            iteration += 1;
-        };
-
-        let final_platform = match cycle {
-            Some(Cycle { offset, length }) => {
-                let equivalent_iterations = offset + ((self.iterations - offset) % length);
-                let mut platform = Platform::clone(item);
-                for _ in 0..equivalent_iterations {
-                    platform.cycle();
-                }
-                platform
-            }
-            None => platform,
-        };
-
-        final_platform.load()
+        let progress = Progress::new("Day 14 cycle search", None);
+        let history = StateHistory::explore(Platform::clone(item), self.iterations, |p| {
+            progress.inc();
+            let mut next = Platform::clone(p);
+            next.cycle();
+            next
+        });
+
+        history
+            .state_after(self.iterations)
+            .expect("Explored until a cycle or the final iteration")
+            .load()
     }
 }
 
 impl DailyChallenge for Day14 {
+    fn title(&self) -> &'static str {
+        "Parabolic Reflector Dish"
+    }
+
+    fn description(&self) -> &'static str {
+        "Tilt a platform of rounded rocks and measure the load after a billion spin cycles."
+    }
+
     fn part1(&self, file: &Path) -> u64 {
         let platform: Platform = fs::read_to_string(file).unwrap().parse().unwrap();
         Part1.solve(&platform)
@@ -327,9 +302,20 @@ impl DailyChallenge for Day14 {
 
     fn part2(&self, file: &Path) -> u64 {
         let platform: Platform = fs::read_to_string(file).unwrap().parse().unwrap();
-        let solver = Part2::new(1_000_000_000);
+        let solver = Part2::new(SPIN_CYCLES);
         solver.solve(&platform)
     }
+
+    /// The platform after tilting north
+    fn visualize(&self, file: &Path) -> Option<String> {
+        let mut platform: Platform = fs::read_to_string(file).ok()?.parse().ok()?;
+        platform.tilt(Direction::North);
+        Some(format!("{platform:?}").trim_start().to_string())
+    }
+
+    fn parameters(&self) -> Vec<(&'static str, String)> {
+        vec![("part2_spin_cycles", SPIN_CYCLES.to_string())]
+    }
 }
 
 #[test]
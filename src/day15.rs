@@ -1,4 +1,5 @@
 use crate::challenge::{DailyChallenge, Solver};
+use crate::parse::{Cursor, ParseError};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{self, Debug};
@@ -13,10 +14,16 @@ pub struct Day15;
 #[derive(Debug)]
 enum Day15Error {
     NotASCII,
-    InvalidAction,
+    Parse(ParseError),
     IOError(io::Error),
 }
 
+impl From<ParseError> for Day15Error {
+    fn from(value: ParseError) -> Self {
+        Self::Parse(value)
+    }
+}
+
 impl From<io::Error> for Day15Error {
     fn from(value: io::Error) -> Self {
         Self::IOError(value)
@@ -30,7 +37,7 @@ impl fmt::Display for Day15Error {
         use Day15Error as E;
         match self {
             E::NotASCII => write!(f, "Value is not ASCII"),
-            E::InvalidAction => write!(f, "Could not parse to action"),
+            E::Parse(e) => std::fmt::Display::fmt(&e, f),
             E::IOError(e) => std::fmt::Display::fmt(&e, f),
         }
     }
@@ -71,6 +78,28 @@ struct Operation {
     action: Action,
 }
 
+impl Operation {
+    /// `label-` or `label=focal_length`
+    fn parse(cursor: &mut Cursor) -> Result<Self, ParseError> {
+        let lens_label = cursor.label()?;
+        let box_number = compute_hash(lens_label.as_bytes()) as u8;
+        let lens_label = String::from(lens_label);
+
+        let action = if cursor.eat("-") {
+            Action::RemoveLens(lens_label)
+        } else {
+            cursor.expect("=")?;
+            let lens = LabelledLens {
+                lens: Lens(cursor.number()?),
+                label: lens_label,
+            };
+            Action::InsertLens(lens)
+        };
+
+        Ok(Operation { box_number, action })
+    }
+}
+
 struct LightFocuser {
     boxes: Vec<LensBox>,
 }
@@ -85,27 +114,10 @@ impl Default for LightFocuser {
 
 impl LightFocuser {
     fn get_operation(&self, instruction: &str) -> Result<Operation, Day15Error> {
-        match instruction.split_once(|c| c == '=' || c == '-') {
-            Some((lens_label, other)) => {
-                let box_number = compute_hash(lens_label.as_bytes()) as u8;
-                let lens_label = String::from(lens_label);
-
-                let action = if other.is_empty() {
-                    Action::RemoveLens(lens_label)
-                } else {
-                    let focal_length: u8 =
-                        other.parse().expect("Instruction should have focal length");
-                    let lens = LabelledLens {
-                        lens: Lens(focal_length),
-                        label: lens_label,
-                    };
-                    Action::InsertLens(lens)
-                };
-
-                Ok(Operation { box_number, action })
-            }
-            None => Err(Day15Error::InvalidAction),
-        }
+        let mut cursor = Cursor::new(1, instruction);
+        let operation = Operation::parse(&mut cursor)?;
+        cursor.finish()?;
+        Ok(operation)
     }
 
     fn apply_operation(&mut self, operation: Operation) {
@@ -154,14 +166,20 @@ struct InitSequence(Vec<String>);
 impl FromStr for InitSequence {
     type Err = Day15Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut strings: Vec<String> = Vec::new();
-        for string in s.split(",") {
-            if !s.is_ascii() {
-                return Err(Day15Error::NotASCII);
-            }
-            strings.push(String::from(string.trim()));
+        if !s.is_ascii() {
+            return Err(Day15Error::NotASCII);
         }
 
+        // Check every step is a valid operation, but keep the raw text
+        // since part 1 hashes it as is
+        let mut cursor = Cursor::new(1, s.trim());
+        let strings = cursor.separated(",", |cursor| {
+            let start = cursor.rest();
+            Operation::parse(cursor)?;
+            Ok(String::from(&start[..start.len() - cursor.rest().len()]))
+        })?;
+        cursor.finish()?;
+
         Ok(Self(strings))
     }
 }
@@ -214,6 +232,14 @@ impl Solver for Part2 {
 }
 
 impl DailyChallenge for Day15 {
+    fn title(&self) -> &'static str {
+        "Lens Library"
+    }
+
+    fn description(&self) -> &'static str {
+        "Hash initialization steps, then follow them to arrange lenses in boxes."
+    }
+
     fn part1(&self, file: &Path) -> u64 {
         let seq: InitSequence = fs::read_to_string(file).unwrap().parse().unwrap();
         Part1.solve(&seq)
@@ -1,189 +1,355 @@
 use crate::challenge::DailyChallenge;
+use crate::parse::{self, Cursor, ParseError};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
 
 #[derive(Default)]
 pub struct Day7;
 
-#[derive(Eq, Ord, Hash, PartialEq, PartialOrd, Clone, Debug)]
-enum CamelCard {
-    Joker,
-    Number(u8),
-    Jack,
-    Queen,
-    King,
-    Ace,
-}
-
-impl CamelCard {
-    fn from_char(ch: char, use_jokers: bool) -> Result<Self, String> {
-        match ch {
-            'A' => Ok(Self::Ace),
-            'K' => Ok(Self::King),
-            'Q' => Ok(Self::Queen),
-            'J' => {
-                if use_jokers {
-                    Ok(Self::Joker)
-                } else {
-                    Ok(Self::Jack)
-                }
-            }
-            'T' => Ok(Self::Number(10)),
-            _ => {
-                if let Some(digit) = ch.to_digit(10) {
-                    Ok(Self::Number(digit as u8))
-                } else {
-                    Err(format!("Cannot convert {ch} to CamelCard"))
-                }
-            }
-        }
-    }
+/// A hand category, matched by the sizes of its groups of equal cards.
+#[derive(Debug, Clone, PartialEq)]
+struct Category {
+    name: String,
+    /// Group sizes, largest first
+    shape: Vec<usize>,
 }
 
-#[derive(Eq, Ord, PartialEq, PartialOrd)]
-enum HandType {
-    HighCard,
-    Pair,
-    TwoPair,
-    ThreeOfAKind,
-    FullHouse,
-    FourOfAKind,
-    FiveOfAKind,
+/// How hands are read and ranked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rules {
+    /// Cards from weakest to strongest
+    card_order: Vec<char>,
+    /// Cards which stand in for whichever card makes the best hand
+    wild: Vec<char>,
+    hand_size: usize,
+    /// Categories from weakest to strongest
+    categories: Vec<Category>,
 }
 
-impl HandType {
-    fn from_cards(cards: &[CamelCard; 5]) -> Self {
-        let mut counts: HashMap<&CamelCard, u8> = HashMap::new();
-        for card in cards {
-            counts
-                .entry(card)
-                .and_modify(|count| *count += 1)
-                .or_insert(1u8);
+const STANDARD_CATEGORIES: [(&str, &[usize]); 7] = [
+    ("high_card", &[1, 1, 1, 1, 1]),
+    ("one_pair", &[2, 1, 1, 1]),
+    ("two_pair", &[2, 2, 1]),
+    ("three_of_a_kind", &[3, 1, 1]),
+    ("full_house", &[3, 2]),
+    ("four_of_a_kind", &[4, 1]),
+    ("five_of_a_kind", &[5]),
+];
+
+impl Rules {
+    fn standard(card_order: &str, wild: &str) -> Self {
+        Self {
+            card_order: card_order.chars().collect(),
+            wild: wild.chars().collect(),
+            hand_size: 5,
+            categories: STANDARD_CATEGORIES
+                .iter()
+                .map(|(name, shape)| Category {
+                    name: name.to_string(),
+                    shape: shape.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn part1() -> Self {
+        Self::standard("23456789TJQKA", "")
+    }
+
+    /// Jacks become jokers, which are wild but the weakest card on a tie
+    pub fn part2() -> Self {
+        Self::standard("J23456789TQKA", "J")
+    }
+
+    pub fn from_file(file: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
+        contents
+            .parse()
+            .map_err(|e: ParseError| format!("{}: {e}", file.display()))
+    }
+
+    fn strength(&self, card: char) -> Option<usize> {
+        self.card_order.iter().position(|&c| c == card)
+    }
+
+    pub fn category_index(&self, name: &str) -> Option<usize> {
+        self.categories.iter().position(|c| c.name == name)
+    }
+
+    /// Counts of the cards which aren't wild, largest first and stronger
+    /// cards first among equal counts.
+    fn groups(&self, cards: &[char]) -> Vec<(usize, char)> {
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        for card in cards.iter().filter(|c| !self.wild.contains(c)) {
+            *counts.entry(*card).or_default() += 1;
+        }
+        let mut groups: Vec<(usize, char)> = counts.into_iter().map(|(c, n)| (n, c)).collect();
+        groups.sort_unstable_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then(self.strength(b.1).cmp(&self.strength(a.1)))
+        });
+        groups
+    }
+
+    /// The strongest category the cards can make once wild cards are
+    /// substituted.
+    fn classify(&self, cards: &[char]) -> Option<usize> {
+        let groups = self.groups(cards);
+
+        // Wild cards can top up existing groups or form new ones, so a shape
+        // fits if the largest groups each fit in a part at least their size
+        let fits = |shape: &[usize]| {
+            groups.len() <= shape.len() && groups.iter().zip(shape).all(|((n, _), part)| n <= part)
+        };
+        self.categories.iter().rposition(|c| fits(&c.shape))
+    }
+
+    /// The cards with each wild card replaced by what it stands in for to
+    /// make `category`: topping up the largest groups first, then starting
+    /// new groups with the strongest cards not already in the hand.
+    fn substitute(&self, cards: &[char], category: usize) -> Vec<char> {
+        let groups = self.groups(cards);
+        let mut unused = self
+            .card_order
+            .iter()
+            .rev()
+            .filter(|c| !self.wild.contains(c) && !cards.contains(c));
+
+        let mut fill = Vec::new();
+        for (i, part) in self.categories[category].shape.iter().enumerate() {
+            let (count, card) = match groups.get(i) {
+                Some(&group) => group,
+                None => match unused.next() {
+                    Some(&card) => (0, card),
+                    None => break,
+                },
+            };
+            fill.extend(std::iter::repeat_n(card, part - count));
         }
 
-        // Handle the effects of Jokers later
-        let mut count_vals_no_jokers: Vec<u8> = counts
+        let mut fill = fill.into_iter();
+        cards
             .iter()
-            .filter_map(|(&k, &v)| {
-                if let CamelCard::Joker = k {
-                    None
-                } else {
-                    Some(v)
-                }
+            .map(|c| match self.wild.contains(c) {
+                true => fill.next().unwrap_or(*c),
+                false => *c,
             })
-            .collect();
+            .collect()
+    }
 
-        count_vals_no_jokers.sort();
-        count_vals_no_jokers.reverse();
-        let mut top_2: Vec<u8> = count_vals_no_jokers.iter().take(2).cloned().collect();
+    fn validate(&self, end: &Cursor) -> Result<(), ParseError> {
+        if self.card_order.is_empty() {
+            return Err(end.error("Missing card order"));
+        }
+        if let Some(card) = self.wild.iter().find(|&&c| self.strength(c).is_none()) {
+            return Err(end.error(format!("Wild card '{card}' is not in the card order")));
+        }
+        if self.categories.is_empty() {
+            return Err(end.error("No hand categories"));
+        }
+        if let Some(c) = self
+            .categories
+            .iter()
+            .find(|c| c.shape.iter().sum::<usize>() != self.hand_size)
+        {
+            return Err(end.error(format!(
+                "Category {} does not have {} cards",
+                c.name, self.hand_size
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Lines of `key = value`, with categories listed weakest first:
+///
+/// ```text
+/// order = 23456789TJQKA
+/// wild = J
+/// hand_size = 5
+/// category = high_card 1 1 1 1 1
+/// ```
+impl FromStr for Rules {
+    type Err = ParseError;
 
-        if let Some(&num_jokers) = counts.get(&CamelCard::Joker) {
-            if num_jokers == 5 {
-                return HandType::FiveOfAKind;
-            } else {
-                top_2[0] = top_2[0] + num_jokers;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = Rules {
+            card_order: Vec::new(),
+            wild: Vec::new(),
+            hand_size: 5,
+            categories: Vec::new(),
+        };
+
+        let entries = parse::lines(s, |cursor| {
+            cursor.skip_whitespace();
+            if cursor.is_at_end() || cursor.eat("#") {
+                cursor.take_while(|_| true);
+                return Ok(*cursor);
             }
-        }
 
-        match top_2[..] {
-            [5] => HandType::FiveOfAKind,
-            [4, 1] => HandType::FourOfAKind,
-            [3, 2] => HandType::FullHouse,
-            [3, 1] => HandType::ThreeOfAKind,
-            [2, 2] => HandType::TwoPair,
-            [2, 1] => HandType::Pair,
-            [1, 1] => HandType::HighCard,
-            _ => unreachable!(),
-        }
+            let key_start = *cursor;
+            let key = cursor.label()?;
+            cursor.skip_whitespace();
+            cursor.expect("=")?;
+            cursor.skip_whitespace();
+
+            let mut cards = || cursor.take_while(|c| !c.is_whitespace()).chars().collect();
+            match key {
+                "order" => rules.card_order = cards(),
+                "wild" => rules.wild = cards(),
+                "hand_size" => rules.hand_size = cursor.number()?,
+                "category" => {
+                    let name = cursor.label()?.to_string();
+                    let mut shape: Vec<usize> = cursor.numbers()?;
+                    shape.sort_unstable_by(|a, b| b.cmp(a));
+                    rules.categories.push(Category { name, shape });
+                }
+                _ => return Err(key_start.error(format!("Unknown rule '{key}'"))),
+            }
+            cursor.skip_whitespace();
+            Ok(*cursor)
+        })?;
+
+        let end = entries.last().copied().unwrap_or(Cursor::new(1, ""));
+        rules.validate(&end)?;
+        Ok(rules)
     }
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq)]
 struct Hand {
-    cards: [CamelCard; 5],
-    hand_type: HandType,
-    bid: u32,
+    cards: Vec<char>,
+    /// Card strengths, for breaking ties between hands of the same category
+    strengths: Vec<usize>,
+    category: usize,
+    bid: u64,
 }
 
 impl Hand {
-    fn new(cards: [CamelCard; 5], bid: u32) -> Self {
-        let hand_type = HandType::from_cards(&cards);
-        Self {
+    fn parse(cursor: &mut Cursor, rules: &Rules) -> Result<Self, ParseError> {
+        let start = *cursor;
+        let strength = |c| rules.strength(c).ok_or(format!("Unknown card '{c}'"));
+        let strengths = cursor.cells(strength)?;
+        if strengths.len() != rules.hand_size {
+            return Err(start.error(format!(
+                "Expected {} cards, found {}",
+                rules.hand_size,
+                strengths.len()
+            )));
+        }
+        let cards: Vec<char> = strengths.iter().map(|&s| rules.card_order[s]).collect();
+        let category = rules
+            .classify(&cards)
+            .ok_or_else(|| start.error("Cards match no hand category"))?;
+
+        if cursor.is_at_end() {
+            return Err(cursor.error("Missing bid"));
+        }
+        cursor.skip_whitespace();
+        let bid = cursor.number()?;
+
+        Ok(Hand {
             cards,
-            hand_type,
+            strengths,
+            category,
             bid,
-        }
+        })
     }
 
-    fn vec_from_file(file: &Path, use_jokers: bool) -> Result<Vec<Self>, String> {
-        let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
+    fn vec_from_str(text: &str, rules: &Rules) -> Result<Vec<Self>, ParseError> {
+        parse::lines(text, |cursor| Self::parse(cursor, rules))
+    }
 
-        contents
-            .lines()
-            .map(|line| {
-                let mut chars = line.chars();
-                let cards: [CamelCard; 5] = chars
-                    .by_ref()
-                    .take(5)
-                    .map(|c| CamelCard::from_char(c, use_jokers))
-                    .collect::<Result<Vec<CamelCard>, String>>()?
-                    .try_into()
-                    .unwrap();
-
-                let bid: u32 = chars
-                    .skip(1)
-                    .collect::<String>()
-                    .parse::<u32>()
-                    .map_err(|e| e.to_string())?;
-
-                Ok(Hand::new(cards, bid))
-            })
-            .collect()
+    fn vec_from_file(file: &Path, rules: &Rules) -> Result<Vec<Self>, String> {
+        let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
+        Self::vec_from_str(&contents, rules).map_err(|e| format!("{}: {e}", file.display()))
     }
 }
 
+/// Hands sorted weakest first along with their ranks.
+fn ranked(mut hands: Vec<Hand>) -> impl Iterator<Item = (Hand, u64)> {
+    hands.sort();
+    hands.into_iter().zip(1..)
+}
+
+/// Summed in `u128` since even `u64` bids times ranks can overflow `u64`.
+fn winnings(hands: Vec<Hand>) -> u128 {
+    ranked(hands)
+        .map(|(hand, rank)| hand.bid as u128 * rank as u128)
+        .sum()
+}
+
 impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
 impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        if self.hand_type == other.hand_type {
-            for (self_card, other_card) in self.cards.iter().zip(other.cards.iter()) {
-                if self_card != other_card {
-                    return self_card.cmp(other_card);
-                }
-            }
-
-            std::cmp::Ordering::Equal
-        } else {
-            self.hand_type.cmp(&other.hand_type)
-        }
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.category
+            .cmp(&other.category)
+            .then_with(|| self.strengths.cmp(&other.strengths))
     }
 }
 
+pub fn total_winnings(file: &Path, rules: &Rules) -> Result<u64, String> {
+    let total = winnings(Hand::vec_from_file(file, rules)?);
+    u64::try_from(total).map_err(|_| format!("Total winnings {total} do not fit in u64"))
+}
+
+/// Why a hand ranked where it did.
+#[derive(Debug, PartialEq)]
+pub struct Explanation {
+    pub cards: String,
+    /// The cards once wild cards are substituted
+    pub played_as: String,
+    pub category: usize,
+    pub category_name: String,
+    /// From 1 for the weakest hand
+    pub rank: u64,
+    pub bid: u64,
+    /// The bid times the rank
+    pub winnings: u128,
+}
+
+/// Explain every hand in the file, weakest first.
+pub fn explain(file: &Path, rules: &Rules) -> Result<Vec<Explanation>, String> {
+    let hands = Hand::vec_from_file(file, rules)?;
+    Ok(ranked(hands)
+        .map(|(hand, rank)| Explanation {
+            cards: hand.cards.iter().collect(),
+            played_as: rules
+                .substitute(&hand.cards, hand.category)
+                .into_iter()
+                .collect(),
+            category: hand.category,
+            category_name: rules.categories[hand.category].name.clone(),
+            rank,
+            bid: hand.bid,
+            winnings: hand.bid as u128 * rank as u128,
+        })
+        .collect())
+}
+
 impl DailyChallenge for Day7 {
+    fn title(&self) -> &'static str {
+        "Camel Cards"
+    }
+
+    fn description(&self) -> &'static str {
+        "Rank poker-like hands of camel cards, with jacks becoming jokers in part 2."
+    }
+
     fn part1(&self, file: &Path) -> u64 {
-        let mut hands = Hand::vec_from_file(&file, false).unwrap();
-        hands.sort();
-        hands
-            .iter()
-            .zip(1..)
-            .map(|(hand, rank)| hand.bid * rank)
-            .sum::<u32>() as u64
+        total_winnings(file, &Rules::part1()).unwrap()
     }
 
     fn part2(&self, file: &Path) -> u64 {
-        let mut hands = Hand::vec_from_file(&file, true).unwrap();
-        hands.sort();
-        hands
-            .iter()
-            .zip(1..)
-            .map(|(hand, rank)| hand.bid * rank)
-            .sum::<u32>() as u64
+        total_winnings(file, &Rules::part2()).unwrap()
     }
 }
 
@@ -196,3 +362,129 @@ fn test_part1() {
 fn test_part2() {
     assert_eq!(Day7.part2(Path::new("data/7.sample")), 5905);
 }
+
+#[test]
+fn test_classify() {
+    let rules = Rules::part2();
+    let classify = |cards: &str| {
+        let cards: Vec<char> = cards.chars().collect();
+        rules.categories[rules.classify(&cards).unwrap()]
+            .name
+            .clone()
+    };
+    assert_eq!(classify("32T3K"), "one_pair");
+    assert_eq!(classify("KTJJT"), "four_of_a_kind");
+    assert_eq!(classify("JJJJJ"), "five_of_a_kind");
+    assert_eq!(classify("2345J"), "one_pair");
+    assert_eq!(classify("22J33"), "full_house");
+}
+
+#[test]
+fn test_explain() {
+    let explanations = explain(Path::new("data/7.sample"), &Rules::part2()).unwrap();
+    let summary: Vec<(&str, &str, &str, u128)> = explanations
+        .iter()
+        .map(|e| {
+            let (cards, played_as) = (e.cards.as_str(), e.played_as.as_str());
+            (cards, played_as, e.category_name.as_str(), e.winnings)
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("32T3K", "32T3K", "one_pair", 765),
+            ("KK677", "KK677", "two_pair", 56),
+            ("T55J5", "T5555", "four_of_a_kind", 2052),
+            ("QQQJA", "QQQQA", "four_of_a_kind", 1932),
+            ("KTJJT", "KTTTT", "four_of_a_kind", 1100),
+        ]
+    );
+    assert_eq!(explanations.iter().map(|e| e.winnings).sum::<u128>(), 5905);
+
+    let rules = Rules::part2();
+    let played_as = |cards: &str| {
+        let cards: Vec<char> = cards.chars().collect();
+        let category = rules.classify(&cards).unwrap();
+        rules
+            .substitute(&cards, category)
+            .into_iter()
+            .collect::<String>()
+    };
+    assert_eq!(played_as("JJJJJ"), "AAAAA");
+    assert_eq!(played_as("2345J"), "23455");
+    assert_eq!(played_as("22J33"), "22333");
+}
+
+#[test]
+fn test_rules_file() {
+    let text = "\
+# Three card hands where twos are wild
+order = 23456789TJQKA
+wild = 2
+
+hand_size = 3
+category = high 1 1 1
+category = pair 1 2
+category = trips 3
+";
+    let rules: Rules = text.parse().unwrap();
+    assert_eq!(rules.categories[1].shape, vec![2, 1]);
+
+    let mut cursor = Cursor::new(1, "A2K 10");
+    let hand = Hand::parse(&mut cursor, &rules).unwrap();
+    assert_eq!(rules.categories[hand.category].name, "pair");
+
+    let mut cursor = Cursor::new(4, "AKQJ 10");
+    let err = Hand::parse(&mut cursor, &rules).unwrap_err();
+    assert_eq!((err.line, err.column), (4, 1));
+
+    let err = "order = AB\nhand_size = 2\ncategory = pair 2\nfoo = 1"
+        .parse::<Rules>()
+        .unwrap_err();
+    assert_eq!((err.line, err.column), (4, 1));
+
+    let err = "order = AB\nwild = C\ncategory = pair 2"
+        .parse::<Rules>()
+        .unwrap_err();
+    assert_eq!(err.message, "Wild card 'C' is not in the card order");
+}
+
+#[test]
+fn test_bad_lines() {
+    let rules = Rules::part1();
+    let error = |text: &str| {
+        let err = Hand::vec_from_str(text, &rules).unwrap_err();
+        (err.line, err.column, err.message)
+    };
+    let short = error("32T3K 765\nT55 684");
+    assert_eq!(short, (2, 1, String::from("Expected 5 cards, found 3")));
+    let missing = error("32T3K 765\nT55J5");
+    assert_eq!(missing, (2, 6, String::from("Missing bid")));
+    assert_eq!(error("32T3K 765\n\nKK677 28").0, 2);
+    assert_eq!(error("32T3K 765\nKK6X7 28").1, 4);
+    assert_eq!(error("32T3K 765\nKK677 28x").1, 9);
+
+    // Any hand size works once the categories add up to it
+    let text = "order = 23456789TJQKA\nhand_size = 7\ncategory = any 1 1 1 1 1 1 1";
+    let rules: Rules = text.parse().unwrap();
+    assert!(Hand::vec_from_str("23456789 1", &rules).is_err());
+    assert_eq!(Hand::vec_from_str("2345678 1", &rules).unwrap().len(), 1);
+}
+
+#[test]
+fn test_large_bids() {
+    let rules = Rules::part1();
+    let hands = |bids: &[u64]| {
+        let lines: Vec<String> = ["23456", "22345", "22334"]
+            .iter()
+            .zip(bids)
+            .map(|(cards, bid)| format!("{cards} {bid}"))
+            .collect();
+        Hand::vec_from_str(&lines.join("\n"), &rules).unwrap()
+    };
+
+    let big = u32::MAX as u64;
+    assert_eq!(winnings(hands(&[big, big, big])), big as u128 * 6);
+    let huge = u64::MAX;
+    assert_eq!(winnings(hands(&[huge, 1, huge])), huge as u128 * 4 + 2);
+}
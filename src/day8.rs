@@ -1,10 +1,16 @@
 use crate::challenge::DailyChallenge;
+use crate::cycle::StateHistory;
+use crate::graph::{self, Graph};
+use crate::math::{self, Congruence, MathError};
+use crate::parallel::par_map;
+use crate::parse::{self, ParseError};
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::iter::{Copied, Cycle};
 use std::path::Path;
 use std::slice::Iter;
+use std::str::FromStr;
 
 #[derive(Default)]
 pub struct Day8;
@@ -36,11 +42,13 @@ impl TryFrom<char> for Direction {
     }
 }
 
+#[derive(Debug)]
 struct NodeEdges {
     left: String,
     right: String,
 }
 
+#[derive(Debug)]
 struct Map {
     directions: Vec<Direction>,
     nodes: HashMap<String, NodeEdges>,
@@ -69,108 +77,482 @@ impl<'a> Iterator for MapIterator<'a> {
     }
 }
 
-const PATTERN: &str = r"([A-Z0-9]{3}) = \(([A-Z0-9]{3}), ([A-Z0-9]{3})\)";
+/// Selects nodes by name, for starts and goals.
+#[derive(Debug, Clone)]
+pub enum NodeMatcher {
+    Name(String),
+    Suffix(String),
+    /// Matches anywhere in the name unless anchored
+    Regex(Regex),
+}
 
+impl NodeMatcher {
+    fn matches(&self, node: &str) -> bool {
+        match self {
+            Self::Name(name) => node == name,
+            Self::Suffix(suffix) => node.ends_with(suffix.as_str()),
+            Self::Regex(regex) => regex.is_match(node),
+        }
+    }
+}
+
+/// `name:AAA`, `suffix:Z` or `regex:^1.A$`, where a bare `AAA` is a name.
+impl FromStr for NodeMatcher {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("name", name)) => Ok(Self::Name(name.to_string())),
+            Some(("suffix", suffix)) => Ok(Self::Suffix(suffix.to_string())),
+            Some(("regex", regex)) => Regex::new(regex)
+                .map(Self::Regex)
+                .map_err(|e| e.to_string()),
+            Some((kind, _)) => Err(format!("Unknown node matcher '{kind}'")),
+            None => Ok(Self::Name(s.to_string())),
+        }
+    }
+}
+
+/// A node paired with the index of the next direction to follow
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 struct LocDir<'a>(&'a str, usize);
 
-impl Map {
-    fn from_file(file: &Path) -> Result<Self, String> {
-        let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
+/// Walking the map as a graph of `LocDir` states, each with one successor
+struct StateGraph<'a> {
+    map: &'a Map,
+}
+
+impl<'a> Graph for StateGraph<'a> {
+    type Node = LocDir<'a>;
+
+    fn edges(&self, state: &LocDir<'a>) -> Vec<(LocDir<'a>, u64)> {
+        let LocDir(node, dir_idx) = *state;
+        let Some(edges) = self.map.nodes.get(node) else {
+            return Vec::new();
+        };
+        let next = self.map.directions[dir_idx].go(edges);
+        let next_idx = (dir_idx + 1) % self.map.directions.len();
+        vec![(LocDir(next, next_idx), 1)]
+    }
+}
+
+impl FromStr for Map {
+    type Err = ParseError;
 
-        let directions = contents
-            .lines()
-            .next()
-            .ok_or("Invalid file format")?
-            .chars()
-            .map(Direction::try_from)
-            .collect::<Result<Vec<Direction>, &str>>()?;
-
-        let mut map = Map {
-            directions,
-            nodes: HashMap::new(),
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [directions, nodes] = parse::blocks(s)[..] else {
+            return Err(ParseError::new(1, 1, "Expected directions and nodes"));
         };
 
-        let re = Regex::new(PATTERN).unwrap();
-        for (_, [source, left, right]) in re.captures_iter(&contents).map(|c| c.extract()) {
-            map.nodes.insert(
-                source.to_string(),
-                NodeEdges {
-                    left: left.to_string(),
-                    right: right.to_string(),
-                },
-            );
+        let directions: Vec<Direction> = directions
+            .lines(|cursor| cursor.cells(Direction::try_from))?
+            .concat();
+        if directions.is_empty() {
+            return Err(ParseError::new(1, 1, "Expected at least one direction"));
+        }
+
+        let first_line = nodes.line;
+        let records = nodes.lines(parse::key_pair)?;
+        let mut nodes: HashMap<String, NodeEdges> = HashMap::new();
+        for (&(source, left, right), line) in records.iter().zip(first_line..) {
+            let edges = NodeEdges {
+                left: left.to_string(),
+                right: right.to_string(),
+            };
+            if nodes.insert(source.to_string(), edges).is_some() {
+                let message = format!("{source} is defined more than once");
+                return Err(ParseError::new(line, 1, message));
+            }
+        }
+
+        // Report edges to nodes which are never defined rather than failing
+        // on arrival
+        for ((source, left, right), line) in records.into_iter().zip(first_line..) {
+            let left_column = source.chars().count() + 5;
+            let right_column = left_column + left.chars().count() + 2;
+            for (target, column) in [(left, left_column), (right, right_column)] {
+                if !nodes.contains_key(target) {
+                    let message = format!("{source} leads to undefined node {target}");
+                    return Err(ParseError::new(line, column, message));
+                }
+            }
         }
 
-        Ok(map)
+        Ok(Map { directions, nodes })
+    }
+}
+
+impl Map {
+    fn from_file(file: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
+        contents.parse().map_err(|e: ParseError| e.to_string())
+    }
+
+    fn states(&self) -> StateGraph<'_> {
+        StateGraph { map: self }
     }
 
-    fn walk<'a>(&'a self, start: &'a str) -> MapIterator {
+    fn step<'a>(&'a self, state: &LocDir<'a>) -> LocDir<'a> {
+        let LocDir(node, dir_idx) = *state;
+        let edges = self.nodes.get(node).expect("Arrived at invalid node");
+        let next = self.directions[dir_idx].go(edges);
+        LocDir(next, (dir_idx + 1) % self.directions.len())
+    }
+
+    fn walk<'a>(&'a self, start: &'a str) -> MapIterator<'a> {
         MapIterator {
             next: start,
             directions: self.directions.iter().copied().cycle(),
             nodes: &self.nodes,
         }
     }
-}
 
-fn steps_to_z(map: &Map, start: &str) -> u32 {
-    let mut taken = 0;
-    let mut map_path = map.walk(start);
-    while let Some(node) = map_path.next() {
-        if node.ends_with("Z") {
-            break;
+    /// Graphviz DOT with nodes matching `from` in green, nodes matching `to`
+    /// in red and the edges along `path` drawn in bold blue.
+    fn to_dot(&self, from: &NodeMatcher, to: &NodeMatcher, path: &[&str]) -> String {
+        let on_path = |from: &str, to: &str| path.windows(2).any(|w| w == [from, to]);
+
+        let mut names: Vec<&String> = self.nodes.keys().collect();
+        names.sort();
+
+        let mut dot = String::from("digraph network {\n");
+        for name in &names {
+            let fill = match name {
+                n if from.matches(n) => ", style=filled, fillcolor=palegreen",
+                n if to.matches(n) => ", style=filled, fillcolor=lightcoral",
+                _ => "",
+            };
+            let id = dot_string(name);
+            dot.push_str(&format!("    {id} [label={id}{fill}];\n"));
         }
-        taken += 1;
+
+        for name in names {
+            let edges = &self.nodes[name];
+            // Both directions to the same node share one edge
+            let targets = match edges.left == edges.right {
+                true => vec![("LR", &edges.left)],
+                false => vec![("L", &edges.left), ("R", &edges.right)],
+            };
+            for (label, to) in targets {
+                let style = match on_path(name, to) {
+                    true => ", color=blue, penwidth=2",
+                    false => "",
+                };
+                dot.push_str(&format!(
+                    "    {} -> {} [label=\"{label}\"{style}];\n",
+                    dot_string(name),
+                    dot_string(to)
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
     }
+}
 
-    taken
+/// A quoted DOT identifier, since node names may contain quotes and
+/// backslashes.
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
 }
 
-fn gcd(mut a: u64, mut b: u64) -> u64 {
-    while b > 0 {
-        let old_b = b;
-        b = a % b;
-        a = old_b;
-    }
-    a
+/// The network in `file` as Graphviz DOT, highlighting the nodes matching
+/// `from` and `to` and optionally the first `steps` steps walked from `start`.
+pub fn render_dot(
+    file: &Path,
+    from: &NodeMatcher,
+    to: &NodeMatcher,
+    start: Option<&str>,
+    steps: usize,
+) -> Result<String, String> {
+    let map = Map::from_file(file)?;
+    let path: Vec<&str> = match start {
+        Some(start) if !map.nodes.contains_key(start) => {
+            return Err(format!("Unknown node {start}"))
+        }
+        Some(start) => map.walk(start).take(steps + 1).collect(),
+        None => Vec::new(),
+    };
+    Ok(map.to_dot(from, to, &path))
 }
 
-fn lcm2(a: u64, b: u64) -> u64 {
-    a * b / gcd(a, b)
+/// When a ghost's walk lands on goal nodes. From `offset` onwards its states
+/// repeat every `length` steps.
+#[derive(Debug, PartialEq)]
+struct GhostCycle {
+    offset: u64,
+    length: u64,
+    /// Steps before the cycle starts which land on a goal
+    lead_in_hits: Vec<u64>,
+    /// Steps in the first lap of the cycle which land on a goal
+    cycle_hits: Vec<u64>,
 }
 
-fn lcm(numbers: &[u64]) -> u64 {
-    numbers.iter().copied().fold(1, lcm2)
+impl GhostCycle {
+    fn analyze(map: &Map, start: &str, goal: &NodeMatcher) -> Self {
+        // Every state is a node and direction index, so one must repeat
+        // within that many steps
+        let max_steps = map.nodes.len() * map.directions.len();
+        let history = StateHistory::explore(LocDir(start, 0), max_steps, |s| map.step(s));
+        let cycle = history.cycle().expect("The walk must repeat a state");
+
+        let (mut lead_in_hits, mut cycle_hits) = (Vec::new(), Vec::new());
+        for (step, state) in history.states().iter().enumerate() {
+            if goal.matches(state.0) {
+                match step < cycle.offset {
+                    true => lead_in_hits.push(step as u64),
+                    false => cycle_hits.push(step as u64),
+                }
+            }
+        }
+
+        Self {
+            offset: cycle.offset as u64,
+            length: cycle.length as u64,
+            lead_in_hits,
+            cycle_hits,
+        }
+    }
+
+    fn first_hit(&self) -> Option<u64> {
+        self.lead_in_hits
+            .first()
+            .or(self.cycle_hits.first())
+            .copied()
+    }
+
+    fn on_goal_at(&self, step: u64) -> bool {
+        if step < self.offset {
+            return self.lead_in_hits.contains(&step);
+        }
+        let lap_step = self.offset + (step - self.offset) % self.length;
+        self.cycle_hits.contains(&lap_step)
+    }
 }
 
-impl DailyChallenge for Day8 {
-    fn part1(&self, file: &Path) -> u64 {
-        let map = Map::from_file(file).unwrap();
-        let mut current = "AAA";
+/// The first step at which every ghost is on a goal.
+fn first_all_on_goal(ghosts: &[GhostCycle]) -> Result<Option<u64>, MathError> {
+    // Until every ghost has reached its cycle, check each step directly
+    let settled = ghosts.iter().map(|g| g.offset).max().unwrap_or(0);
+    if let Some(step) = (0..settled).find(|&s| ghosts.iter().all(|g| g.on_goal_at(s))) {
+        return Ok(Some(step));
+    }
 
-        for (step, dir) in map.directions.iter().cycle().enumerate() {
-            if current == "ZZZ" {
-                return step as u64;
+    // After that each ghost is on a goal at the steps congruent to one of its
+    // cycle hits. Merging ghost by ghost keeps one congruence per distinct
+    // residue rather than one per combination of hits.
+    let mut congruences = vec![Congruence::new(0, 1)?];
+    for ghost in ghosts {
+        let mut merged = Vec::new();
+        for &congruence in &congruences {
+            for &hit in &ghost.cycle_hits {
+                let cycle = Congruence::new(hit as i128, ghost.length as i128)?;
+                match math::crt(&[congruence, cycle]) {
+                    Ok(c) if !merged.contains(&c) => merged.push(c),
+                    Ok(_) | Err(MathError::NoSolution) => {}
+                    Err(e) => return Err(e),
+                }
             }
+        }
+        congruences = merged;
+    }
 
-            let edges = map.nodes.get(current).unwrap();
-            current = dir.go(&edges);
+    // The earliest step of each congruence once every ghost has settled
+    let mut first = None;
+    for c in congruences {
+        let behind = (settled as i128 - c.residue).max(0) as u128;
+        let laps = behind.div_ceil(c.modulus as u128) as i128;
+        let step = u64::try_from(c.residue + laps * c.modulus).map_err(|_| MathError::Overflow)?;
+        first = first.min(Some(step)).or(Some(step));
+    }
+    Ok(first)
+}
+
+impl Map {
+    /// Steps until walkers from every node matching `from` are on nodes
+    /// matching `to` at the same time, along with what the usual LCM
+    /// shortcut would have given (`None` if it overflows) so callers can
+    /// flag inputs where it is wrong.
+    fn steps(&self, from: &NodeMatcher, to: &NodeMatcher) -> Result<(u64, Option<u64>), String> {
+        let mut starts: Vec<&String> = self.nodes.keys().filter(|n| from.matches(n)).collect();
+        starts.sort();
+        if starts.is_empty() {
+            return Err(String::from("No node matches the start"));
+        }
+        if !self.nodes.keys().any(|n| to.matches(n)) {
+            return Err(String::from("No node matches the goal"));
+        }
+
+        let ghosts: Vec<GhostCycle> = par_map(&starts, |n| GhostCycle::analyze(self, n, to));
+        if let Some((start, _)) = starts
+            .iter()
+            .zip(&ghosts)
+            .find(|(_, g)| g.first_hit().is_none())
+        {
+            return Err(format!("No goal is reachable from {start}"));
         }
 
-        panic!();
+        let steps = match first_all_on_goal(&ghosts) {
+            Ok(Some(steps)) => steps,
+            Ok(None) => return Err(String::from("The walkers are never all on goals at once")),
+            Err(_) => return Err(String::from("Step count does not fit in u64")),
+        };
+
+        // The usual shortcut assumes each ghost first reaches a goal after
+        // exactly one lap of a cycle with no other goals
+        let first_hits: Option<Vec<u64>> = ghosts.iter().map(GhostCycle::first_hit).collect();
+        let shortcut = first_hits.and_then(|hits| math::lcm_all(&hits));
+
+        Ok((steps, shortcut))
     }
+}
 
-    fn part2(&self, file: &Path) -> u64 {
-        let map = Map::from_file(file).unwrap();
-        let paths: Vec<u64> = map
+impl Map {
+    /// Steps from AAA to ZZZ following the directions from the start.
+    fn aaa_to_zzz(&self) -> Result<usize, String> {
+        match self.shortest("AAA", 0, &NodeMatcher::Name("ZZZ".into()))? {
+            Some((_, steps)) => Ok(steps),
+            None => Err(String::from("ZZZ is not reachable from AAA")),
+        }
+    }
+}
+
+pub fn steps(
+    file: &Path,
+    from: &NodeMatcher,
+    to: &NodeMatcher,
+) -> Result<(u64, Option<u64>), String> {
+    Map::from_file(file)?.steps(from, to)
+}
+
+/// Queries for exploring a network. Walks start at a node with `phase`
+/// directions already followed, since the same node can lead somewhere
+/// else at another point in the directions.
+impl Map {
+    fn state<'a>(&self, node: &'a str, phase: usize) -> Result<LocDir<'a>, String> {
+        if !self.nodes.contains_key(node) {
+            return Err(format!("Unknown node {node}"));
+        }
+        Ok(LocDir(node, phase % self.directions.len()))
+    }
+
+    /// The first goal reached from `from` and the steps taken to get there.
+    fn shortest<'a>(
+        &'a self,
+        from: &'a str,
+        phase: usize,
+        to: &NodeMatcher,
+    ) -> Result<Option<(&'a str, usize)>, String> {
+        let start = self.state(from, phase)?;
+        let found = graph::bfs_find(&self.states(), start, |s| to.matches(s.0));
+        Ok(found.map(|(LocDir(node, _), steps)| (node, steps)))
+    }
+
+    /// Every node visited by walking from `from`, in name order.
+    fn reachable<'a>(&'a self, from: &'a str, phase: usize) -> Result<Vec<&'a str>, String> {
+        let start = self.state(from, phase)?;
+        let mut nodes: Vec<&str> = graph::bfs_distances(&self.states(), start)
+            .into_keys()
+            .map(|LocDir(node, _)| node)
+            .collect();
+        nodes.sort();
+        nodes.dedup();
+        Ok(nodes)
+    }
+
+    /// Nodes from which no walk reaches a goal, whatever the phase.
+    fn never_reaching(&self, to: &NodeMatcher) -> Vec<&str> {
+        // Search backwards from every goal state, following the edges of
+        // the state graph in reverse
+        let mut predecessors: HashMap<LocDir, Vec<LocDir>> = HashMap::new();
+        for node in self.nodes.keys() {
+            for phase in 0..self.directions.len() {
+                let state = LocDir(node.as_str(), phase);
+                predecessors
+                    .entry(self.step(&state))
+                    .or_default()
+                    .push(state);
+            }
+        }
+
+        let mut queue: VecDeque<LocDir> = predecessors
+            .keys()
+            .chain(predecessors.values().flatten())
+            .filter(|s| to.matches(s.0))
+            .copied()
+            .collect();
+        let mut reaching: HashSet<LocDir> = queue.iter().copied().collect();
+        while let Some(state) = queue.pop_front() {
+            for &previous in predecessors.get(&state).into_iter().flatten() {
+                if reaching.insert(previous) {
+                    queue.push_back(previous);
+                }
+            }
+        }
+
+        let mut nodes: Vec<&str> = self
             .nodes
             .keys()
-            .filter(|n| n.ends_with("A"))
-            .map(|n| steps_to_z(&map, n) as u64)
+            .map(String::as_str)
+            .filter(|node| (0..self.directions.len()).all(|p| !reaching.contains(&LocDir(node, p))))
             .collect();
+        nodes.sort();
+        nodes
+    }
+}
+
+/// The first goal reached from a node and how many steps it takes, or
+/// `None` if no goal is reachable.
+pub fn shortest(
+    file: &Path,
+    from: &str,
+    phase: usize,
+    to: &NodeMatcher,
+) -> Result<Option<(String, usize)>, String> {
+    let map = Map::from_file(file)?;
+    let found = map.shortest(from, phase, to)?;
+    Ok(found.map(|(node, steps)| (node.to_string(), steps)))
+}
 
-        lcm(&paths)
+pub fn reachable(file: &Path, from: &str, phase: usize) -> Result<Vec<String>, String> {
+    let map = Map::from_file(file)?;
+    let nodes = map.reachable(from, phase)?;
+    Ok(nodes.into_iter().map(String::from).collect())
+}
+
+pub fn never_reaching(file: &Path, to: &NodeMatcher) -> Result<Vec<String>, String> {
+    let map = Map::from_file(file)?;
+    Ok(map
+        .never_reaching(to)
+        .into_iter()
+        .map(String::from)
+        .collect())
+}
+
+impl DailyChallenge for Day8 {
+    fn title(&self) -> &'static str {
+        "Haunted Wasteland"
+    }
+
+    fn description(&self) -> &'static str {
+        "Follow left/right instructions through a network of nodes, then from every start node at once."
+    }
+
+    fn part1(&self, file: &Path) -> u64 {
+        let map = Map::from_file(file).unwrap();
+        map.aaa_to_zzz().unwrap_or_else(|e| panic!("{e}")) as u64
+    }
+
+    fn part2(&self, file: &Path) -> u64 {
+        let map = Map::from_file(file).unwrap();
+        let (from, to) = (
+            NodeMatcher::Suffix("A".into()),
+            NodeMatcher::Suffix("Z".into()),
+        );
+        match map.steps(&from, &to) {
+            Ok((steps, _)) => steps,
+            Err(e) => panic!("{e}"),
+        }
     }
 }
 
@@ -188,8 +570,172 @@ mod tests {
         assert_eq!(Day8.part1(Path::new("data/8.sample2")), 6);
     }
 
+    #[test]
+    fn test_part1_errors() {
+        let map: Map = "L\n\nAAA = (AAA, ZZZ)\nZZZ = (ZZZ, ZZZ)".parse().unwrap();
+        assert_eq!(
+            map.aaa_to_zzz(),
+            Err(String::from("ZZZ is not reachable from AAA"))
+        );
+        let map: Map = "L\n\nBBB = (ZZZ, ZZZ)\nZZZ = (ZZZ, ZZZ)".parse().unwrap();
+        assert_eq!(map.aaa_to_zzz(), Err(String::from("Unknown node AAA")));
+    }
+
     #[test]
     fn test_part2() {
         assert_eq!(Day8.part2(Path::new("data/8.sample3")), 6);
     }
+
+    #[test]
+    fn test_ghost_cycles() {
+        let map: Map = fs::read_to_string("data/8.sample3")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let z = NodeMatcher::Suffix("Z".into());
+        let ghost = GhostCycle::analyze(&map, "22A", &z);
+        assert_eq!(
+            ghost,
+            GhostCycle {
+                offset: 1,
+                length: 6,
+                lead_in_hits: vec![],
+                cycle_hits: vec![3, 6],
+            }
+        );
+        assert!(ghost.on_goal_at(9) && !ghost.on_goal_at(10));
+
+        // 11A is on 11Z only once before settling into a three step cycle,
+        // so the LCM of the first hits (2) is wrong
+        let text = "\
+L
+
+11A = (11Z, 11Z)
+11Z = (11B, 11B)
+11B = (11C, 11C)
+11C = (12Z, 12Z)
+12Z = (11B, 11B)
+22A = (22B, 22B)
+22B = (22Z, 22Z)
+22Z = (22B, 22B)
+33A = (33Z, 33Z)
+33Z = (33Z, 33Z)";
+        let map: Map = text.parse().unwrap();
+        let ghosts = [
+            GhostCycle::analyze(&map, "11A", &z),
+            GhostCycle::analyze(&map, "22A", &z),
+            GhostCycle::analyze(&map, "33A", &z),
+        ];
+        assert_eq!(
+            ghosts[0],
+            GhostCycle {
+                offset: 2,
+                length: 3,
+                lead_in_hits: vec![1],
+                cycle_hits: vec![4],
+            }
+        );
+        assert!(ghosts[0].on_goal_at(7) && !ghosts[0].on_goal_at(8));
+        assert_eq!(ghosts[0].first_hit(), Some(1));
+        assert_eq!(ghosts[1].first_hit(), Some(2));
+        assert_eq!(first_all_on_goal(&ghosts[..2]), Ok(Some(4)));
+        // Both are on a goal during 11A's lead-in
+        let ghosts = [
+            GhostCycle::analyze(&map, "11A", &z),
+            GhostCycle::analyze(&map, "33A", &z),
+        ];
+        assert_eq!(first_all_on_goal(&ghosts), Ok(Some(1)));
+
+        let a = NodeMatcher::Suffix("A".into());
+        assert_eq!(map.steps(&a, &z), Ok((4, Some(2))));
+    }
+
+    #[test]
+    fn test_matchers() {
+        let text = "LR\n\nstart = (mid-1, end.2)\nmid-1 = (mid-1, end.2)\nend.2 = (end.2, end.2)";
+        let map: Map = text.parse().unwrap();
+        let matcher = |s: &str| s.parse::<NodeMatcher>().unwrap();
+        assert_eq!(
+            map.steps(&matcher("start"), &matcher("regex:^end")),
+            Ok((2, Some(2)))
+        );
+        assert_eq!(
+            map.steps(&matcher("name:mid-1"), &matcher("suffix:.2")),
+            Ok((2, Some(2)))
+        );
+        assert!(matches!(matcher("regex:[0-9]"), NodeMatcher::Regex(_)));
+        assert!("shape:x".parse::<NodeMatcher>().is_err());
+        assert!("regex:(".parse::<NodeMatcher>().is_err());
+
+        assert_eq!(
+            map.steps(&matcher("end.2"), &matcher("start")),
+            Err(String::from("No goal is reachable from end.2"))
+        );
+        assert!(map.steps(&matcher("nowhere"), &matcher("start")).is_err());
+        assert!(map.steps(&matcher("start"), &matcher("nowhere")).is_err());
+
+        let err = "L\n\nAAA = (BBB, AAA)\nBBB = (AAA, QQQ)"
+            .parse::<Map>()
+            .unwrap_err();
+        assert_eq!((err.line, err.column), (4, 13));
+        assert_eq!(err.message, "BBB leads to undefined node QQQ");
+
+        let err = "L\n\nAAA = (BBB, BBB)\nBBB = (AAA, AAA)\nAAA = (AAA, AAA)"
+            .parse::<Map>()
+            .unwrap_err();
+        assert_eq!((err.line, err.column), (5, 1));
+        assert_eq!(err.message, "AAA is defined more than once");
+    }
+
+    #[test]
+    fn test_queries() {
+        let map = Map::from_file(Path::new("data/8.sample3")).unwrap();
+        let z = NodeMatcher::Suffix("Z".into());
+        assert_eq!(map.shortest("11A", 0, &z), Ok(Some(("11Z", 2))));
+        // 11B only leads to 11Z when going right
+        assert_eq!(map.shortest("11B", 0, &z), Ok(None));
+        assert_eq!(map.shortest("11B", 1, &z), Ok(Some(("11Z", 1))));
+        assert_eq!(map.shortest("11B", 3, &z), Ok(Some(("11Z", 1))));
+        assert!(map.shortest("QQQ", 0, &z).is_err());
+
+        assert_eq!(
+            map.reachable("22A", 0),
+            Ok(vec!["22A", "22B", "22C", "22Z"])
+        );
+        assert_eq!(map.reachable("11B", 0), Ok(vec!["11B", "XXX"]));
+
+        assert_eq!(map.never_reaching(&z), vec!["XXX"]);
+        let to_22 = NodeMatcher::Name("22Z".into());
+        assert_eq!(map.never_reaching(&to_22), vec!["11A", "11B", "11Z", "XXX"]);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let map = Map::from_file(Path::new("data/8.sample2")).unwrap();
+        let path: Vec<&str> = map.walk("AAA").take(3).collect();
+        assert_eq!(path, ["AAA", "BBB", "AAA"]);
+
+        let (a, z) = (
+            NodeMatcher::Suffix("A".into()),
+            NodeMatcher::Suffix("Z".into()),
+        );
+        let dot = map.to_dot(&a, &z, &path);
+        assert!(dot.starts_with("digraph network {\n"));
+        assert!(dot.contains("\"AAA\" [label=\"AAA\", style=filled, fillcolor=palegreen];"));
+        assert!(dot.contains("\"ZZZ\" [label=\"ZZZ\", style=filled, fillcolor=lightcoral];"));
+        assert!(dot.contains("\"AAA\" -> \"BBB\" [label=\"LR\", color=blue, penwidth=2];"));
+        assert!(dot.contains("\"BBB\" -> \"AAA\" [label=\"L\", color=blue, penwidth=2];"));
+        assert!(dot.contains("\"BBB\" -> \"ZZZ\" [label=\"R\"];"));
+
+        let dot = map.to_dot(&NodeMatcher::Name("BBB".into()), &a, &[]);
+        assert!(dot.contains("\"BBB\" [label=\"BBB\", style=filled, fillcolor=palegreen];"));
+        assert!(dot.contains("\"AAA\" [label=\"AAA\", style=filled, fillcolor=lightcoral];"));
+        assert!(dot.contains("\"ZZZ\" [label=\"ZZZ\"];"));
+
+        let map: Map = "L\n\na\"b = (c\\d, c\\d)\nc\\d = (c\\d, c\\d)"
+            .parse()
+            .unwrap();
+        let dot = map.to_dot(&a, &z, &[]);
+        assert!(dot.contains("    \"a\\\"b\" -> \"c\\\\d\" [label=\"LR\"];"));
+    }
 }
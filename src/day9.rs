@@ -1,92 +1,251 @@
+use crate::bigint::{BigInt, Integer};
 use crate::challenge::DailyChallenge;
+use crate::parallel::par_map;
+use crate::parse::{self, ParseError};
+use std::fmt;
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
 
 #[derive(Default)]
 pub struct Day9;
 
 #[derive(Eq, PartialEq, Debug)]
-struct History(Vec<i32>);
+struct History<T = i64>(Vec<T>);
 
-impl History {
-    fn diff(&self) -> Self {
-        Self(self.0.windows(2).map(|pair| pair[1] - pair[0]).collect())
+/// A sequence in Newton's forward difference form: the value at index `n`
+/// is the sum of `C(n, k)` times the `k`th difference at index 0.
+#[derive(Eq, PartialEq, Debug)]
+struct Newton<T> {
+    /// The first entry of each row of the difference table, up to the last
+    /// row which is not all zeros
+    coefficients: Vec<T>,
+}
+
+impl<T: Integer + From<i64>> Newton<T> {
+    /// `None` for a sequence of zeros
+    fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
     }
 
-    fn all_zero(&self) -> bool {
-        return self.0.iter().all(|&h| h == 0);
+    /// The value at any index, including before the start. `None` if it
+    /// overflows `T`.
+    fn at(&self, n: i64) -> Option<T> {
+        let mut total = T::zero();
+        let mut binomial = <T as From<u32>>::from(1);
+        for (k, coefficient) in self.coefficients.iter().enumerate() {
+            if k > 0 {
+                // C(n, k) = C(n, k - 1) * (n - k + 1) / k, which divides exactly
+                let factor = T::from(n.checked_sub(k as i64 - 1)?);
+                binomial = binomial.checked_mul(&factor)?.div_small(k as u32);
+            }
+            total = total.checked_add(&binomial.checked_mul(coefficient)?)?;
+        }
+        Some(total)
     }
+}
 
-    fn most_recent(&self) -> Option<i32> {
-        self.0.last().copied()
+impl<T: Integer + fmt::Display> fmt::Display for Newton<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.coefficients.is_empty() {
+            return write!(f, "0");
+        }
+        let terms: Vec<String> = self
+            .coefficients
+            .iter()
+            .enumerate()
+            .map(|(k, c)| match k {
+                0 => c.to_string(),
+                _ => format!("{c}*C(n,{k})"),
+            })
+            .collect();
+        write!(f, "{}", terms.join(" + "))
     }
+}
+
+#[derive(Debug, PartialEq)]
+enum HistoryError {
+    Overflow,
+    /// The differences only vanish once there is a single value left, so
+    /// the history does not pin down a polynomial
+    NotPolynomial,
+}
 
-    fn predict(&self) -> i32 {
-        if self.all_zero() {
-            0
-        } else {
-            self.most_recent().unwrap_or(0) + self.diff().predict()
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoryError::Overflow => write!(f, "Arithmetic overflow"),
+            HistoryError::NotPolynomial => {
+                write!(f, "Differences never reach all zeros within the history")
+            }
         }
     }
+}
 
-    fn extrapolate(&self) -> i32 {
-        if self.all_zero() {
-            0
-        } else {
-            self.0.first().copied().unwrap_or(0) - self.diff().extrapolate()
+impl<T: Integer + From<i64>> History<T> {
+    /// The history's Newton form, built in the history's own buffer.
+    fn newton(self) -> Result<Newton<T>, HistoryError> {
+        // Take differences in place, from the back so each entry is still
+        // the previous row's when it is used. Afterwards entry `k` is the
+        // `k`th difference at index 0.
+        let mut coefficients = self.0;
+        let len = coefficients.len();
+        for row in 1..len {
+            for i in (row..len).rev() {
+                coefficients[i] = coefficients[i]
+                    .checked_sub(&coefficients[i - 1])
+                    .ok_or(HistoryError::Overflow)?;
+            }
         }
+
+        if coefficients.last() != Some(&T::zero()) {
+            return Err(HistoryError::NotPolynomial);
+        }
+        while coefficients.last() == Some(&T::zero()) {
+            coefficients.pop();
+        }
+        Ok(Newton { coefficients })
+    }
+
+    fn predict(self) -> Result<T, HistoryError> {
+        let len = self.0.len() as i64;
+        self.newton()?.at(len).ok_or(HistoryError::Overflow)
+    }
+
+    fn extrapolate(self) -> Result<T, HistoryError> {
+        self.newton()?.at(-1).ok_or(HistoryError::Overflow)
     }
 }
 
-impl FromIterator<i32> for History {
-    fn from_iter<T: IntoIterator<Item = i32>>(iter: T) -> Self {
-        Self(iter.into_iter().collect::<Vec<i32>>())
+impl History {
+    /// A copy of the values in `T`
+    fn widen<T: From<i64>>(&self) -> History<T> {
+        History(self.0.iter().map(|&h| T::from(h)).collect())
+    }
+}
+
+impl<T> FromIterator<T> for History<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().collect::<Vec<T>>())
     }
 }
 
 #[derive(Eq, PartialEq, Debug)]
 struct OASISReport {
-    histories: Vec<History>,
+    /// Each history with the line it was read from
+    histories: Vec<(usize, History)>,
 }
 
-impl OASISReport {
-    fn from_file(file: &Path) -> Result<Self, String> {
-        let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
+/// Why a report could not be summed
+#[derive(Debug, PartialEq)]
+enum SumError {
+    Overflow,
+    /// The line of a history which is not a polynomial
+    NotPolynomial(usize),
+}
 
-        let histories = contents
-            .lines()
-            .map(|l| {
-                l.split_whitespace()
-                    .map(|n| n.parse::<i32>().map_err(|e| e.to_string()))
-                    .collect::<Result<History, String>>()
-            })
-            .collect::<Result<Vec<History>, String>>()?;
+impl fmt::Display for SumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SumError::Overflow => write!(f, "{}", HistoryError::Overflow),
+            SumError::NotPolynomial(line) => {
+                write!(f, "Line {line}: {}", HistoryError::NotPolynomial)
+            }
+        }
+    }
+}
 
+impl FromStr for OASISReport {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let histories = parse::lines(s, |cursor| Ok((cursor.line(), History(cursor.numbers()?))))?;
         Ok(Self { histories })
     }
+}
+
+impl OASISReport {
+    fn from_file(file: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
+        contents.parse().map_err(|e: ParseError| e.to_string())
+    }
 
-    fn predict_all(&self) -> Vec<i32> {
-        self.histories.iter().map(History::predict).collect()
+    /// Sum `f` over every history in `T`.
+    fn sum<T>(&self, f: fn(History<T>) -> Result<T, HistoryError>) -> Result<T, SumError>
+    where
+        T: Integer + From<i64> + Send,
+    {
+        let values = par_map(&self.histories, |(line, h)| (*line, f(h.widen())));
+
+        let mut total = Some(T::zero());
+        for (line, value) in values {
+            match value {
+                Ok(value) => total = total.and_then(|t| t.checked_add(&value)),
+                Err(HistoryError::Overflow) => total = None,
+                Err(HistoryError::NotPolynomial) => return Err(SumError::NotPolynomial(line)),
+            }
+        }
+        total.ok_or(SumError::Overflow)
     }
 
-    fn extrapolate_all(&self) -> Vec<i32> {
-        self.histories.iter().map(History::extrapolate).collect()
+    /// Sum `f` in `i64`, and only if that overflows recompute exactly with
+    /// `exact`, so any total which fits in `u64` is an answer.
+    fn answer(
+        &self,
+        f: fn(History) -> Result<i64, HistoryError>,
+        exact: fn(History<BigInt>) -> Result<BigInt, HistoryError>,
+    ) -> Result<u64, String> {
+        match self.sum(f) {
+            Ok(total) => u64::try_from(total).map_err(|_| format!("Sum {total} is negative")),
+            Err(SumError::Overflow) => {
+                let total = self.sum(exact).map_err(|e| e.to_string())?;
+                total
+                    .to_u64()
+                    .ok_or_else(|| format!("Sum {total} does not fit in u64"))
+            }
+            Err(e) => Err(e.to_string()),
+        }
     }
 }
 
 impl DailyChallenge for Day9 {
+    fn title(&self) -> &'static str {
+        "Mirage Maintenance"
+    }
+
+    fn description(&self) -> &'static str {
+        "Extrapolate sensor histories forwards and backwards using repeated differences."
+    }
+
     fn part1(&self, file: &Path) -> u64 {
         let report = OASISReport::from_file(file).unwrap();
-        report.predict_all().iter().map(|&h| h as i64).sum::<i64>() as u64
+        report
+            .answer(History::predict, History::predict)
+            .unwrap_or_else(|e| panic!("{e}"))
     }
 
     fn part2(&self, file: &Path) -> u64 {
         let report = OASISReport::from_file(file).unwrap();
         report
-            .extrapolate_all()
+            .answer(History::extrapolate, History::extrapolate)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Each history as the polynomial through its values
+    fn visualize(&self, file: &Path) -> Option<String> {
+        let report = OASISReport::from_file(file).ok()?;
+        let lines: Vec<String> = report
+            .histories
             .iter()
-            .map(|&h| h as i64)
-            .sum::<i64>() as u64
+            .map(|(_, h)| match h.widen::<BigInt>().newton() {
+                Ok(newton) => match newton.degree() {
+                    Some(degree) => format!("degree {degree}: {newton}"),
+                    None => String::from("zero: 0"),
+                },
+                Err(e) => e.to_string(),
+            })
+            .collect();
+        Some(lines.join("\n"))
     }
 }
 
@@ -95,9 +254,9 @@ fn test_from_file() {
     let report = OASISReport::from_file(Path::new("data/9.sample")).expect("Test file missing");
     let expected = OASISReport {
         histories: vec![
-            History(vec![0, 3, 6, 9, 12, 15]),
-            History(vec![1, 3, 6, 10, 15, 21]),
-            History(vec![10, 13, 16, 21, 30, 45]),
+            (1, History(vec![0, 3, 6, 9, 12, 15])),
+            (2, History(vec![1, 3, 6, 10, 15, 21])),
+            (3, History(vec![10, 13, 16, 21, 30, 45])),
         ],
     };
     assert_eq!(report, expected);
@@ -112,3 +271,95 @@ fn test_part1() {
 fn test_part2() {
     assert_eq!(Day9.part2(Path::new("data/9.sample")), 2)
 }
+
+#[test]
+fn test_overflow() {
+    let history = History(vec![0, 1 << 61, 1 << 62, 3 << 61]);
+    // The next value is 2^63, one more than i64::MAX
+    assert_eq!(
+        history.widen::<i64>().predict(),
+        Err(HistoryError::Overflow)
+    );
+    assert_eq!(
+        history.widen::<BigInt>().predict().map(|p| p.to_string()),
+        Ok(String::from("9223372036854775808"))
+    );
+}
+
+#[test]
+fn test_newton() {
+    let linear = History(vec![0i64, 3, 6, 9, 12, 15]).newton().unwrap();
+    assert_eq!(linear.coefficients, vec![0, 3]);
+    assert_eq!(linear.degree(), Some(1));
+    assert_eq!(linear.at(1000), Some(3000));
+    assert_eq!(linear.at(-1000), Some(-3000));
+
+    // Triangular numbers, (n + 1)(n + 2) / 2
+    let triangular = History(vec![1i64, 3, 6, 10, 15, 21]);
+    let quadratic = triangular.widen::<i64>().newton().unwrap();
+    assert_eq!(quadratic.to_string(), "1 + 2*C(n,1) + 1*C(n,2)");
+    assert_eq!(quadratic.at(1000), Some(501501));
+    assert_eq!(quadratic.at(-1002), Some(500500));
+    assert_eq!(History(vec![0i64, 0]).newton().unwrap().degree(), None);
+
+    let polynomials = Day9.visualize(Path::new("data/9.sample")).unwrap();
+    assert!(polynomials.starts_with("degree 1: 0 + 3*C(n,1)\ndegree 2: 1 + 2*C(n,1) + 1*C(n,2)\n"));
+
+    // Exact far beyond i64
+    let far = i64::MAX / 2;
+    assert_eq!(quadratic.at(far), None);
+    let exact = triangular.widen::<BigInt>().newton().unwrap();
+    let expected = &(&BigInt::from(far + 1) * &BigInt::from(far + 2)).div_small(2);
+    assert_eq!(exact.at(far).as_ref(), Some(expected));
+}
+
+#[test]
+fn test_not_polynomial() {
+    // Powers of two keep doubling however many times they are differenced
+    let doubling = || History(vec![1i64, 2, 4, 8, 16]);
+    assert_eq!(doubling().newton(), Err(HistoryError::NotPolynomial));
+    assert_eq!(doubling().predict(), Err(HistoryError::NotPolynomial));
+    assert_eq!(
+        History(vec![5i64]).predict(),
+        Err(HistoryError::NotPolynomial)
+    );
+
+    assert_eq!(History(vec![7i64, 7]).predict(), Ok(7));
+    assert_eq!(History(vec![1i64, 2, 4, 7]).predict(), Ok(11));
+    assert_eq!(
+        History(vec![1i64, 2, 4, 8]).predict(),
+        Err(HistoryError::NotPolynomial)
+    );
+}
+
+#[test]
+fn test_answer() {
+    // Blank lines before the first history still count
+    let report: OASISReport = "\n0 3 6 9\n1 2 4 8\n".parse().unwrap();
+    assert_eq!(
+        report.sum(History::<i64>::predict),
+        Err(SumError::NotPolynomial(3))
+    );
+    assert_eq!(
+        report.answer(History::predict, History::predict),
+        Err(String::from(
+            "Line 3: Differences never reach all zeros within the history"
+        ))
+    );
+
+    // 2^63 overflows i64 but is still an answer
+    let report: OASISReport = "0 2305843009213693952 4611686018427387904 6917529027641081856"
+        .parse()
+        .unwrap();
+    assert_eq!(report.sum(History::<i64>::predict), Err(SumError::Overflow));
+    assert_eq!(
+        report.answer(History::predict, History::predict),
+        Ok(1 << 63)
+    );
+
+    let report: OASISReport = "0 -1 -2".parse().unwrap();
+    assert_eq!(
+        report.answer(History::predict, History::predict),
+        Err(String::from("Sum -3 is negative"))
+    );
+}
@@ -0,0 +1,201 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+const SESSION_ENV: &str = "AOC_SESSION";
+const BASE_URL_ENV: &str = "AOC_BASE_URL";
+const LAST_FETCH_FILE: &str = ".last_fetch";
+
+#[derive(Debug)]
+pub enum FetchError {
+    MissingSession,
+    Http(String),
+    IOError(io::Error),
+}
+
+impl From<io::Error> for FetchError {
+    fn from(value: io::Error) -> Self {
+        Self::IOError(value)
+    }
+}
+
+impl Error for FetchError {}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use FetchError as E;
+        match self {
+            E::MissingSession => write!(
+                f,
+                "No session token: set {SESSION_ENV} or write it to {}",
+                session_file().display()
+            ),
+            E::Http(e) => write!(f, "Request failed: {e}"),
+            E::IOError(e) => fmt::Display::fmt(&e, f),
+        }
+    }
+}
+
+pub struct FetchConfig {
+    pub base_url: String,
+    pub year: u16,
+    pub session: String,
+    pub min_interval: Duration,
+}
+
+fn session_file() -> PathBuf {
+    let home = std::env::var_os("HOME").unwrap_or_default();
+    Path::new(&home).join(".config/aoc/session")
+}
+
+impl FetchConfig {
+    /// Build a config from the environment, falling back to the session file
+    /// when `AOC_SESSION` is not set.
+    pub fn from_env(
+        base_url: Option<String>,
+        year: u16,
+        min_interval: Duration,
+    ) -> Result<Self, FetchError> {
+        let session = match std::env::var(SESSION_ENV) {
+            Ok(session) => session,
+            Err(_) => fs::read_to_string(session_file()).map_err(|_| FetchError::MissingSession)?,
+        };
+        let session = session.trim().to_string();
+        if session.is_empty() {
+            return Err(FetchError::MissingSession);
+        }
+
+        let base_url = base_url
+            .or_else(|| std::env::var(BASE_URL_ENV).ok())
+            .unwrap_or_else(|| String::from(DEFAULT_BASE_URL));
+
+        Ok(Self {
+            base_url,
+            year,
+            session,
+            min_interval,
+        })
+    }
+
+    fn url(&self, day: u8) -> String {
+        format!(
+            "{}/{}/day/{day}/input",
+            self.base_url.trim_end_matches('/'),
+            self.year
+        )
+    }
+}
+
+pub fn input_path(data: &Path, day: u8) -> PathBuf {
+    data.join(format!("{day}.input"))
+}
+
+/// Wait until `min_interval` has passed since the last request made from
+/// `data`, across runs, then record this request.
+fn rate_limit(data: &Path, min_interval: Duration) -> io::Result<()> {
+    let stamp_file = data.join(LAST_FETCH_FILE);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let last = fs::read_to_string(&stamp_file)
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .map(Duration::from_millis);
+    if let Some(last) = last {
+        let wait = (last + min_interval).saturating_sub(now);
+        if !wait.is_zero() {
+            eprintln!("Rate limited: waiting {:.1}s", wait.as_secs_f64());
+            thread::sleep(wait);
+        }
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    fs::write(stamp_file, now.as_millis().to_string())
+}
+
+/// Download the input for `day` into `data`, unless it is already there.
+/// Returns the path of the input file.
+pub fn fetch_input(config: &FetchConfig, day: u8, data: &Path) -> Result<PathBuf, FetchError> {
+    let path = input_path(data, day);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    fs::create_dir_all(data)?;
+    rate_limit(data, config.min_interval)?;
+
+    let input = ureq::get(&config.url(day))
+        .set("Cookie", &format!("session={}", config.session))
+        .set("User-Agent", "github.com/aaron-lucas/advent-of-code-2023")
+        .call()
+        .map_err(|e| FetchError::Http(e.to_string()))?
+        .into_string()?;
+
+    fs::write(&path, input)?;
+    Ok(path)
+}
+
+#[test]
+fn test_fetch_input() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let (requests, received) = mpsc::channel();
+
+    // Mock server which answers a single request
+    thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut head: Vec<String> = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim().is_empty() {
+                break;
+            }
+            head.push(line.trim().to_string());
+        }
+        requests.send(head).unwrap();
+
+        let body = "1 2 3\n";
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+    });
+
+    let data = std::env::temp_dir().join(format!("aoc-fetch-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&data);
+    let config = FetchConfig {
+        base_url,
+        year: 2023,
+        session: String::from("secret"),
+        min_interval: Duration::ZERO,
+    };
+
+    let path = fetch_input(&config, 9, &data).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "1 2 3\n");
+
+    let head = received.recv().unwrap();
+    assert_eq!(head[0], "GET /2023/day/9/input HTTP/1.1");
+    assert!(head.iter().any(|h| h == "Cookie: session=secret"));
+
+    // The mock server has gone away, so this only succeeds without a request
+    assert_eq!(fetch_input(&config, 9, &data).unwrap(), path);
+
+    fs::remove_dir_all(&data).unwrap();
+}
@@ -0,0 +1,440 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+#[cfg(test)]
+use std::{cmp::Reverse, collections::BinaryHeap, marker::PhantomData};
+
+/// A directed graph with weighted edges. Nodes only need to be discoverable
+/// from their neighbours, so implicit graphs such as grids work as well as
+/// explicit adjacency maps.
+pub trait Graph {
+    type Node: Clone + Eq + Hash;
+
+    fn edges(&self, node: &Self::Node) -> Vec<(Self::Node, u64)>;
+
+    fn neighbours(&self, node: &Self::Node) -> Vec<Self::Node> {
+        self.edges(node).into_iter().map(|(n, _)| n).collect()
+    }
+}
+
+impl<N: Clone + Eq + Hash> Graph for HashMap<N, Vec<N>> {
+    type Node = N;
+
+    fn edges(&self, node: &N) -> Vec<(N, u64)> {
+        self.get(node)
+            .map(|ns| ns.iter().map(|n| (n.clone(), 1)).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl<N: Clone + Eq + Hash> Graph for HashMap<N, Vec<(N, u64)>> {
+    type Node = N;
+
+    fn edges(&self, node: &N) -> Vec<(N, u64)> {
+        self.get(node).cloned().unwrap_or_default()
+    }
+}
+
+/// A graph whose edges are computed by a function.
+#[cfg(test)]
+pub struct FnGraph<N, F> {
+    edges: F,
+    node: PhantomData<N>,
+}
+
+#[cfg(test)]
+impl<N, F> FnGraph<N, F>
+where
+    N: Clone + Eq + Hash,
+    F: Fn(&N) -> Vec<(N, u64)>,
+{
+    pub fn new(edges: F) -> Self {
+        Self {
+            edges,
+            node: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+impl<N, F> Graph for FnGraph<N, F>
+where
+    N: Clone + Eq + Hash,
+    F: Fn(&N) -> Vec<(N, u64)>,
+{
+    type Node = N;
+
+    fn edges(&self, node: &N) -> Vec<(N, u64)> {
+        (self.edges)(node)
+    }
+}
+
+/// The up to four orthogonal neighbours of `(row, col)` in a grid.
+#[cfg(test)]
+pub fn grid_neighbours(
+    rows: usize,
+    cols: usize,
+    (row, col): (usize, usize),
+) -> Vec<(usize, usize)> {
+    let mut neighbours = Vec::with_capacity(4);
+    if row > 0 {
+        neighbours.push((row - 1, col));
+    }
+    if row + 1 < rows {
+        neighbours.push((row + 1, col));
+    }
+    if col > 0 {
+        neighbours.push((row, col - 1));
+    }
+    if col + 1 < cols {
+        neighbours.push((row, col + 1));
+    }
+    neighbours
+}
+
+/// Number of edges on the shortest path to every node reachable from `start`.
+pub fn bfs_distances<G: Graph>(graph: &G, start: G::Node) -> HashMap<G::Node, usize> {
+    let mut distances = HashMap::from([(start.clone(), 0)]);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(node) = queue.pop_front() {
+        let distance = distances[&node];
+        for next in graph.neighbours(&node) {
+            if !distances.contains_key(&next) {
+                distances.insert(next.clone(), distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    distances
+}
+
+/// The closest node to `start` satisfying `is_goal`, and its distance.
+pub fn bfs_find<G, P>(graph: &G, start: G::Node, is_goal: P) -> Option<(G::Node, usize)>
+where
+    G: Graph,
+    P: Fn(&G::Node) -> bool,
+{
+    let mut seen = HashSet::from([start.clone()]);
+    let mut queue = VecDeque::from([(start, 0)]);
+
+    while let Some((node, distance)) = queue.pop_front() {
+        if is_goal(&node) {
+            return Some((node, distance));
+        }
+        for next in graph.neighbours(&node) {
+            if seen.insert(next.clone()) {
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+
+    None
+}
+
+/// Nodes reachable from `start` in depth-first preorder.
+pub fn dfs<G: Graph>(graph: &G, start: G::Node) -> Vec<G::Node> {
+    let mut seen = HashSet::new();
+    let mut order = Vec::new();
+    let mut stack = vec![start];
+
+    while let Some(node) = stack.pop() {
+        if !seen.insert(node.clone()) {
+            continue;
+        }
+        let mut neighbours = graph.neighbours(&node);
+        neighbours.retain(|n| !seen.contains(n));
+        stack.extend(neighbours.into_iter().rev());
+        order.push(node);
+    }
+
+    order
+}
+
+/// Lowest total edge weight to every node reachable from `start`.
+#[cfg(test)]
+pub fn dijkstra<G: Graph>(graph: &G, start: G::Node) -> HashMap<G::Node, u64> {
+    let mut distances: HashMap<G::Node, u64> = HashMap::from([(start.clone(), 0)]);
+    let mut heap = BinaryHeap::from([(Reverse(0), 0)]);
+    let mut nodes = vec![start];
+
+    while let Some((Reverse(distance), index)) = heap.pop() {
+        let node = nodes[index].clone();
+        if distances.get(&node).is_some_and(|&d| d < distance) {
+            continue;
+        }
+
+        for (next, weight) in graph.edges(&node) {
+            let candidate = distance + weight;
+            if distances.get(&next).is_none_or(|&d| candidate < d) {
+                distances.insert(next.clone(), candidate);
+                heap.push((Reverse(candidate), nodes.len()));
+                nodes.push(next);
+            }
+        }
+    }
+
+    distances
+}
+
+/// Cheapest path from `start` to a node satisfying `is_goal`. The
+/// `heuristic` must never overestimate the remaining cost.
+#[cfg(test)]
+pub fn astar<G, P, H>(
+    graph: &G,
+    start: G::Node,
+    is_goal: P,
+    heuristic: H,
+) -> Option<(u64, Vec<G::Node>)>
+where
+    G: Graph,
+    P: Fn(&G::Node) -> bool,
+    H: Fn(&G::Node) -> u64,
+{
+    let mut costs: HashMap<G::Node, u64> = HashMap::from([(start.clone(), 0)]);
+    let mut parents: HashMap<G::Node, G::Node> = HashMap::new();
+    let mut heap = BinaryHeap::from([(Reverse(heuristic(&start)), Reverse(0), 0)]);
+    let mut nodes = vec![start];
+
+    while let Some((_, Reverse(cost), index)) = heap.pop() {
+        let node = nodes[index].clone();
+        if costs.get(&node).is_some_and(|&c| c < cost) {
+            continue;
+        }
+
+        if is_goal(&node) {
+            let mut path = vec![node];
+            while let Some(parent) = parents.get(path.last().unwrap()) {
+                path.push(parent.clone());
+            }
+            path.reverse();
+            return Some((cost, path));
+        }
+
+        for (next, weight) in graph.edges(&node) {
+            let candidate = cost + weight;
+            if costs.get(&next).is_none_or(|&c| candidate < c) {
+                costs.insert(next.clone(), candidate);
+                parents.insert(next.clone(), node.clone());
+                let estimate = candidate + heuristic(&next);
+                heap.push((Reverse(estimate), Reverse(candidate), nodes.len()));
+                nodes.push(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// Strongly connected components of the nodes reachable from `roots`, using
+/// Tarjan's algorithm. Components are returned in reverse topological order.
+#[cfg(test)]
+pub fn strongly_connected_components<G: Graph>(graph: &G, roots: &[G::Node]) -> Vec<Vec<G::Node>> {
+    let mut index: HashMap<G::Node, usize> = HashMap::new();
+    let mut low_link: HashMap<G::Node, usize> = HashMap::new();
+    let mut on_stack: HashSet<G::Node> = HashSet::new();
+    let mut stack: Vec<G::Node> = Vec::new();
+    let mut components = Vec::new();
+
+    for root in roots {
+        if index.contains_key(root) {
+            continue;
+        }
+
+        // Explicit call stack of (node, its neighbours, next neighbour to visit)
+        let mut calls = vec![(root.clone(), graph.neighbours(root), 0)];
+        index.insert(root.clone(), index.len());
+        low_link.insert(root.clone(), index[root]);
+        stack.push(root.clone());
+        on_stack.insert(root.clone());
+
+        while let Some((node, neighbours, next)) = calls.last_mut() {
+            if let Some(child) = neighbours.get(*next).cloned() {
+                *next += 1;
+                if !index.contains_key(&child) {
+                    let child_index = index.len();
+                    index.insert(child.clone(), child_index);
+                    low_link.insert(child.clone(), child_index);
+                    stack.push(child.clone());
+                    on_stack.insert(child.clone());
+                    let child_neighbours = graph.neighbours(&child);
+                    calls.push((child, child_neighbours, 0));
+                } else if on_stack.contains(&child) {
+                    let low = low_link[node].min(index[&child]);
+                    low_link.insert(node.clone(), low);
+                }
+                continue;
+            }
+
+            let node = node.clone();
+            calls.pop();
+            if let Some((parent, _, _)) = calls.last() {
+                let low = low_link[parent].min(low_link[&node]);
+                low_link.insert(parent.clone(), low);
+            }
+
+            if low_link[&node] == index[&node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack.remove(&member);
+                    let done = member == node;
+                    component.push(member);
+                    if done {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+/// Nodes reachable from `roots` ordered so every edge points forwards, or
+/// `None` if there is a cycle.
+#[cfg(test)]
+pub fn topological_sort<G: Graph>(graph: &G, roots: &[G::Node]) -> Option<Vec<G::Node>> {
+    let components = strongly_connected_components(graph, roots);
+    let mut order = Vec::with_capacity(components.len());
+    for mut component in components.into_iter().rev() {
+        let node = component.pop()?;
+        if !component.is_empty() || graph.neighbours(&node).contains(&node) {
+            return None;
+        }
+        order.push(node);
+    }
+    Some(order)
+}
+
+/// Every simple cycle among the nodes reachable from `roots`. The number of
+/// cycles can grow exponentially, so this is only for small or sparse graphs.
+#[cfg(test)]
+pub fn simple_cycles<G: Graph>(graph: &G, roots: &[G::Node]) -> Vec<Vec<G::Node>> {
+    let mut cycles = Vec::new();
+
+    for component in strongly_connected_components(graph, roots) {
+        let position: HashMap<&G::Node, usize> =
+            component.iter().enumerate().map(|(i, n)| (n, i)).collect();
+
+        // Find cycles whose lowest positioned node is `start`, so each cycle
+        // is reported once
+        for (start_pos, start) in component.iter().enumerate() {
+            let mut path = vec![start.clone()];
+            let mut calls = vec![graph.neighbours(start)];
+
+            while let Some(candidates) = calls.last_mut() {
+                let Some(next) = candidates.pop() else {
+                    calls.pop();
+                    path.pop();
+                    continue;
+                };
+
+                if next == *start {
+                    cycles.push(path.clone());
+                } else if position.get(&next).is_some_and(|&p| p > start_pos)
+                    && !path.contains(&next)
+                {
+                    calls.push(graph.neighbours(&next));
+                    path.push(next);
+                }
+            }
+        }
+    }
+
+    cycles
+}
+
+#[test]
+fn test_bfs() {
+    let graph: HashMap<u32, Vec<u32>> =
+        HashMap::from([(1, vec![2, 3]), (2, vec![4]), (3, vec![4]), (4, vec![5])]);
+    let distances = bfs_distances(&graph, 1);
+    assert_eq!(distances[&4], 2);
+    assert_eq!(distances[&5], 3);
+    assert_eq!(bfs_find(&graph, 1, |&n| n > 3), Some((4, 2)));
+    assert_eq!(bfs_find(&graph, 1, |&n| n > 5), None);
+    assert_eq!(dfs(&graph, 1), vec![1, 2, 4, 5, 3]);
+}
+
+#[test]
+fn test_weighted_paths() {
+    let graph: HashMap<char, Vec<(char, u64)>> = HashMap::from([
+        ('a', vec![('b', 7), ('c', 9), ('f', 14)]),
+        ('b', vec![('c', 10), ('d', 15)]),
+        ('c', vec![('d', 11), ('f', 2)]),
+        ('d', vec![('e', 6)]),
+        ('f', vec![('e', 9)]),
+    ]);
+    let distances = dijkstra(&graph, 'a');
+    assert_eq!(distances[&'e'], 20);
+    assert_eq!(distances[&'d'], 20);
+
+    let (cost, path) = astar(&graph, 'a', |&n| n == 'e', |_| 0).unwrap();
+    assert_eq!(cost, 20);
+    assert_eq!(path, vec!['a', 'c', 'f', 'e']);
+}
+
+#[test]
+fn test_grid_astar() {
+    // Walls down column 2 except for the bottom row
+    let walls = [(0, 2), (1, 2), (2, 2), (3, 2)];
+    let grid = FnGraph::new(|&cell: &(usize, usize)| {
+        grid_neighbours(5, 5, cell)
+            .into_iter()
+            .filter(|n| !walls.contains(n))
+            .map(|n| (n, 1))
+            .collect()
+    });
+    let goal = (0, 4);
+    let manhattan = |&(r, c): &(usize, usize)| (r.abs_diff(goal.0) + c.abs_diff(goal.1)) as u64;
+
+    let (cost, path) = astar(&grid, (0, 0), |&n| n == goal, manhattan).unwrap();
+    assert_eq!(cost, 12);
+    assert_eq!(path.len(), 13);
+    assert_eq!(bfs_distances(&grid, (0, 0))[&goal], 12);
+}
+
+#[test]
+fn test_components_and_order() {
+    let graph: HashMap<u32, Vec<u32>> = HashMap::from([
+        (1, vec![2]),
+        (2, vec![3]),
+        (3, vec![1, 4]),
+        (4, vec![5]),
+        (5, vec![4]),
+    ]);
+    let mut components: Vec<Vec<u32>> = strongly_connected_components(&graph, &[1])
+        .into_iter()
+        .map(|mut c| {
+            c.sort();
+            c
+        })
+        .collect();
+    components.sort();
+    assert_eq!(components, vec![vec![1, 2, 3], vec![4, 5]]);
+    assert_eq!(topological_sort(&graph, &[1]), None);
+
+    let dag: HashMap<u32, Vec<u32>> =
+        HashMap::from([(1, vec![2, 3]), (2, vec![4]), (3, vec![4]), (4, vec![])]);
+    let order = topological_sort(&dag, &[1]).unwrap();
+    let position = |n| order.iter().position(|&x| x == n).unwrap();
+    assert!(position(1) < position(2) && position(2) < position(4));
+    assert!(position(1) < position(3) && position(3) < position(4));
+}
+
+#[test]
+fn test_simple_cycles() {
+    let graph: HashMap<u32, Vec<u32>> =
+        HashMap::from([(1, vec![2]), (2, vec![1, 3]), (3, vec![1, 3])]);
+    let mut cycles: Vec<Vec<u32>> = simple_cycles(&graph, &[1])
+        .into_iter()
+        .map(|mut c| {
+            let min = c.iter().position(|n| n == c.iter().min().unwrap()).unwrap();
+            c.rotate_left(min);
+            c
+        })
+        .collect();
+    cycles.sort();
+    assert_eq!(cycles, vec![vec![1, 2], vec![1, 2, 3], vec![3]]);
+}
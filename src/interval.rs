@@ -0,0 +1,229 @@
+use std::ops::Range;
+
+/// A set of integers stored as sorted, disjoint, non-adjacent half-open
+/// ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<Range<i64>>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ranges(&self) -> &[Range<i64>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Total number of integers in the set.
+    pub fn len(&self) -> i64 {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        let after = self.ranges.partition_point(|r| r.end <= value);
+        self.ranges.get(after).is_some_and(|r| r.start <= value)
+    }
+
+    pub fn insert(&mut self, range: Range<i64>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // Ranges which overlap or touch the new one are merged into it
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        let merged = match self.ranges.get(first..last) {
+            Some([head, .., tail]) => head.start.min(range.start)..tail.end.max(range.end),
+            Some([only]) => only.start.min(range.start)..only.end.max(range.end),
+            _ => range,
+        };
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        for range in &other.ranges {
+            result.insert(range.clone());
+        }
+        result
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.ranges.get(i), other.ranges.get(j)) {
+            let overlap = a.start.max(b.start)..a.end.min(b.end);
+            if !overlap.is_empty() {
+                ranges.push(overlap);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for range in &self.ranges {
+            let mut start = range.start;
+            while let Some(cut) = other.ranges.get(j) {
+                if cut.end <= start {
+                    j += 1;
+                    continue;
+                }
+                if cut.start >= range.end {
+                    break;
+                }
+                if cut.start > start {
+                    ranges.push(start..cut.start);
+                }
+                start = cut.end;
+                if cut.end > range.end {
+                    break;
+                }
+                j += 1;
+            }
+            if start < range.end {
+                ranges.push(start..range.end);
+            }
+        }
+        Self { ranges }
+    }
+
+    /// The parts of the set below `at`, and at or above it.
+    pub fn split_at(&self, at: i64) -> (Self, Self) {
+        let below = Self::from(i64::MIN..at);
+        (self.intersection(&below), self.difference(&below))
+    }
+
+    /// Every member moved by `offset`.
+    pub fn shift(&self, offset: i64) -> Self {
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|r| (r.start + offset)..(r.end + offset))
+            .collect();
+        Self { ranges }
+    }
+}
+
+impl From<Range<i64>> for IntervalSet {
+    fn from(range: Range<i64>) -> Self {
+        let mut set = Self::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl FromIterator<Range<i64>> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Range<i64>>>(iter: T) -> Self {
+        let mut set = Self::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+/// One stage of a piecewise mapping: values inside a source range are
+/// shifted by its offset, and values outside every source range are kept.
+#[derive(Debug, Default)]
+pub struct RangeMapping {
+    pieces: Vec<(Range<i64>, i64)>,
+}
+
+impl RangeMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, source: Range<i64>, offset: i64) {
+        self.pieces.push((source, offset));
+    }
+
+    pub fn apply(&self, input: &IntervalSet) -> IntervalSet {
+        let mut unmapped = input.clone();
+        let mut mapped = IntervalSet::new();
+        for (source, offset) in &self.pieces {
+            let source = IntervalSet::from(source.clone());
+            mapped = mapped.union(&unmapped.intersection(&source).shift(*offset));
+            unmapped = unmapped.difference(&source);
+        }
+        mapped.union(&unmapped)
+    }
+}
+
+#[test]
+fn test_against_bitset() {
+    const UNIVERSE: i64 = 8;
+
+    fn from_mask(mask: u32) -> IntervalSet {
+        // Insert in a scrambled order to exercise merging
+        (0..UNIVERSE)
+            .map(|x| (x * 5) % UNIVERSE)
+            .filter(|x| mask & (1 << x) != 0)
+            .map(|x| x..x + 1)
+            .collect()
+    }
+
+    fn to_mask(set: &IntervalSet) -> u32 {
+        (0..UNIVERSE)
+            .filter(|&x| set.contains(x))
+            .fold(0, |mask, x| mask | (1 << x))
+    }
+
+    for a in 0..(1u32 << UNIVERSE) {
+        let set_a = from_mask(a);
+        assert_eq!(to_mask(&set_a), a);
+        assert_eq!(set_a.len(), a.count_ones() as i64);
+        assert_eq!(set_a.is_empty(), a == 0);
+
+        // Stored ranges are sorted, non-empty and never touch
+        for pair in set_a.ranges().windows(2) {
+            assert!(pair[0].end < pair[1].start);
+        }
+        assert!(set_a.ranges().iter().all(|r| !r.is_empty()));
+
+        for b in 0..(1u32 << UNIVERSE) {
+            let set_b = from_mask(b);
+            assert_eq!(to_mask(&set_a.union(&set_b)), a | b);
+            assert_eq!(to_mask(&set_a.intersection(&set_b)), a & b);
+            assert_eq!(to_mask(&set_a.difference(&set_b)), a & !b);
+        }
+
+        for at in 0..=UNIVERSE {
+            let (below, above) = set_a.split_at(at);
+            let low_bits = (1 << at) - 1;
+            assert_eq!(to_mask(&below), a & low_bits);
+            assert_eq!(to_mask(&above), a & !low_bits);
+        }
+
+        let shifted = set_a.shift(3).shift(-3);
+        assert_eq!(shifted, set_a);
+    }
+}
+
+#[test]
+fn test_range_mapping() {
+    // Seed-to-soil map from the 2023 day 5 example
+    let mut mapping = RangeMapping::new();
+    mapping.add(98..100, 50 - 98);
+    mapping.add(50..98, 52 - 50);
+
+    let seeds: IntervalSet = [79..93, 55..68].into_iter().collect();
+    let soil = mapping.apply(&seeds);
+    assert_eq!(soil.ranges(), &[57..70, 81..95]);
+
+    let edges = mapping.apply(&IntervalSet::from(96..102));
+    assert_eq!(edges.ranges(), &[50..52, 98..102]);
+}
@@ -1,9 +1,15 @@
-use clap::{Parser, ValueEnum};
-use std::path::Path;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+mod allocs;
+mod bigint;
 mod challenge;
 use challenge::DailyChallenge;
 
+mod cycle;
 mod day10;
 mod day11;
 mod day12;
@@ -13,40 +19,639 @@ mod day15;
 mod day7;
 mod day8;
 mod day9;
+mod fetch;
+mod graph;
+// Not used by any day yet, only by its own tests
+#[cfg(test)]
+mod interval;
+mod math;
+mod memo;
+mod parallel;
+mod parse;
+mod perf;
+mod progress;
+mod report;
+mod samples;
+mod serve;
 
-#[derive(ValueEnum, Clone)]
+#[global_allocator]
+static ALLOCATOR: allocs::CountingAlloc = allocs::CountingAlloc;
+
+#[derive(ValueEnum, Clone, Copy)]
 enum Mode {
     Part1,
     Part2,
 }
 
+#[derive(ValueEnum, Clone, Copy)]
+enum HandOrder {
+    Rank,
+    Type,
+    Bid,
+    Winnings,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum Render {
+    /// Graphviz DOT, e.g. piped into `dot -Tsvg`
+    Dot,
+}
+
+impl Mode {
+    fn from_number(part: u8) -> Option<Self> {
+        match part {
+            1 => Some(Mode::Part1),
+            2 => Some(Mode::Part2),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Parser)]
-struct Args {
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    /// Number of worker threads to use
+    #[arg(long, short, global = true, default_value_t = 1)]
+    jobs: usize,
+
+    /// Report allocation counts, bytes allocated and peak live bytes for
+    /// each part. Parts are then run one at a time so counts stay separate.
+    #[arg(long, global = true)]
+    profile_alloc: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    solve: Option<SolveArgs>,
+}
+
+#[derive(Args)]
+struct SolveArgs {
     day: u8,
     mode: Mode,
     file: String,
+    /// How day 10 part 2 counts enclosed tiles: `shoelace` or `scanline`
+    #[arg(long, default_value = "shoelace")]
+    day10_engine: day10::AreaEngine,
 }
 
-fn main() {
-    let challenges: Vec<Box<dyn DailyChallenge>> = vec![
+#[derive(Subcommand)]
+enum Command {
+    /// Run both parts of every day on `<data>/<day>.input`
+    All {
+        #[arg(long, default_value = "data")]
+        data: PathBuf,
+        /// How day 10 part 2 counts enclosed tiles: `shoelace` or `scanline`
+        #[arg(long, default_value = "shoelace")]
+        day10_engine: day10::AreaEngine,
+    },
+    /// Score day 7 camel card hands under a puzzle part's rules or a rules file
+    Hands {
+        file: PathBuf,
+        #[arg(long, value_enum, default_value_t = Mode::Part1, conflicts_with = "rules")]
+        preset: Mode,
+        /// Rules for a variant game, as `key = value` lines setting `order`,
+        /// `wild`, `hand_size` and one `category` per hand type
+        #[arg(long)]
+        rules: Option<PathBuf>,
+        /// List each hand's type, what its wild cards became, rank and winnings
+        #[arg(long)]
+        explain: bool,
+        #[arg(long, value_enum, default_value_t = HandOrder::Rank, requires = "explain")]
+        sort: HandOrder,
+        /// Only list hands of this type, such as `full_house`
+        #[arg(long, requires = "explain")]
+        filter: Option<String>,
+    },
+    /// Render a day 8 network of nodes
+    Network {
+        file: PathBuf,
+        #[arg(long, value_enum)]
+        render: Render,
+        /// Highlight the start nodes
+        #[arg(long, default_value = "suffix:A")]
+        from: day8::NodeMatcher,
+        /// Highlight the goal nodes
+        #[arg(long, default_value = "suffix:Z")]
+        to: day8::NodeMatcher,
+        /// Highlight the path walked from this node
+        #[arg(long)]
+        start: Option<String>,
+        /// Number of steps of the path to highlight
+        #[arg(long, default_value_t = 20, requires = "start")]
+        steps: usize,
+    },
+    /// Count the steps until walkers from every start in a day 8 network are
+    /// on goals at once. Nodes are matched by `name:AAA` (or just `AAA`),
+    /// `suffix:Z` or `regex:^.*Z$`.
+    Walk {
+        file: PathBuf,
+        #[arg(long, default_value = "AAA")]
+        from: day8::NodeMatcher,
+        #[arg(long, default_value = "ZZZ")]
+        to: day8::NodeMatcher,
+    },
+    /// Explore a day 8 network: distances, reachable nodes and dead ends
+    Query {
+        #[command(subcommand)]
+        action: QueryCommand,
+    },
+    /// Serve an HTTP/JSON API for solving on localhost
+    Serve {
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+    /// Download the puzzle input for a day into the data directory
+    Fetch {
+        day: u8,
+        #[arg(long, default_value = "data")]
+        data: PathBuf,
+        /// Defaults to $AOC_BASE_URL, then https://adventofcode.com
+        #[arg(long)]
+        base_url: Option<String>,
+        #[arg(long, default_value_t = 2023)]
+        year: u16,
+        /// Minimum number of seconds between requests
+        #[arg(long, default_value_t = 5)]
+        min_interval: u64,
+    },
+    /// Record benchmarks in the performance log, or compare logged results
+    Perf {
+        #[command(subcommand)]
+        action: PerfCommand,
+    },
+    /// Run every day and write a Markdown report and a standalone HTML page
+    Report {
+        #[arg(long, default_value = "data")]
+        data: PathBuf,
+        #[arg(long, default_value = "report.md")]
+        markdown: PathBuf,
+        #[arg(long, default_value = "report.html")]
+        html: PathBuf,
+        /// How day 10 part 2 counts enclosed tiles: `shoelace` or `scanline`
+        #[arg(long, default_value = "shoelace")]
+        day10_engine: day10::AreaEngine,
+    },
+    /// Extract example inputs and answers from a saved puzzle page
+    Samples {
+        page: PathBuf,
+        #[arg(long)]
+        day: u8,
+        #[arg(long, default_value = "data")]
+        data: PathBuf,
+        #[arg(long, default_value = samples::MANIFEST_FILE)]
+        manifest: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum QueryCommand {
+    /// The fewest steps from a node to a goal
+    Steps {
+        file: PathBuf,
+        #[arg(long)]
+        from: String,
+        #[arg(long)]
+        to: day8::NodeMatcher,
+        /// Number of directions already followed when the walk starts
+        #[arg(long, default_value_t = 0)]
+        phase: usize,
+    },
+    /// Every node a walk from a node passes through
+    Reachable {
+        file: PathBuf,
+        #[arg(long)]
+        from: String,
+        #[arg(long, default_value_t = 0)]
+        phase: usize,
+    },
+    /// Nodes from which no walk ever reaches a goal
+    Stuck {
+        file: PathBuf,
+        #[arg(long)]
+        to: day8::NodeMatcher,
+    },
+}
+
+#[derive(Subcommand)]
+enum PerfCommand {
+    /// Time each part on `<data>/<day>.input` and append the results to the log
+    Record {
+        #[arg(long, default_value = "data")]
+        data: PathBuf,
+        #[arg(long, default_value_t = 5)]
+        runs: usize,
+        #[arg(long, default_value = "perf.log")]
+        log: PathBuf,
+    },
+    /// Flag parts whose median time slowed against a baseline commit
+    Compare {
+        baseline: String,
+        /// Defaults to the commit of the newest record
+        #[arg(long)]
+        current: Option<String>,
+        /// Percentage slowdown above which a part counts as a regression
+        #[arg(long, default_value_t = 10.0)]
+        threshold: f64,
+        #[arg(long, default_value = "perf.log")]
+        log: PathBuf,
+    },
+}
+
+const FIRST_DAY: u8 = 7;
+
+fn challenges() -> Vec<Box<dyn DailyChallenge>> {
+    challenges_with(day10::AreaEngine::default())
+}
+
+/// Every day, with day 10 counting enclosed tiles using `day10_engine`
+fn challenges_with(day10_engine: day10::AreaEngine) -> Vec<Box<dyn DailyChallenge>> {
+    vec![
         Box::new(day7::Day7::default()),
         Box::new(day8::Day8::default()),
         Box::new(day9::Day9::default()),
-        Box::new(day10::Day10::default()),
+        Box::new(day10::Day10::new(day10_engine)),
         Box::new(day11::Day11::default()),
         Box::new(day12::Day12::default()),
         Box::new(day13::Day13::default()),
         Box::new(day14::Day14::default()),
         Box::new(day15::Day15::default()),
-    ];
+    ]
+}
 
-    let args = Args::parse();
-    let path = Path::new(&args.file);
-    let index = (args.day as usize) - (7_usize);
-    let challenge = &challenges[index];
-    let result = match args.mode {
+fn run_part(challenge: &dyn DailyChallenge, mode: Mode, path: &Path) -> Result<u64, String> {
+    catch_panic(|| match mode {
         Mode::Part1 => challenge.part1(path),
         Mode::Part2 => challenge.part2(path),
+    })
+}
+
+/// Run `f`, turning a panic into its message
+fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|e| {
+        e.downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| e.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| String::from("Solver panicked"))
+    })
+}
+
+fn run_all(data: &Path, day10_engine: day10::AreaEngine, profile_alloc: bool) {
+    let challenges = challenges_with(day10_engine);
+    let days: Vec<(u8, &dyn DailyChallenge)> = challenges
+        .iter()
+        .zip(FIRST_DAY..)
+        .map(|(c, day)| (day, c.as_ref()))
+        .collect();
+
+    let run_day = |&(day, challenge): &(u8, &dyn DailyChallenge)| {
+        let path = fetch::input_path(data, day);
+        if !path.exists() {
+            return vec![format!("day {day}: missing {}", path.display())];
+        }
+
+        [("part1", Mode::Part1), ("part2", Mode::Part2)]
+            .iter()
+            .map(|&(name, mode)| {
+                let (result, stats) = allocs::measure(|| run_part(challenge, mode, &path));
+                let stats = if profile_alloc {
+                    format!(" [{stats}]")
+                } else {
+                    String::new()
+                };
+                match result {
+                    Ok(result) => format!("day {day} {name}: {result}{stats}"),
+                    Err(e) => format!("day {day} {name}: error: {e}{stats}"),
+                }
+            })
+            .collect()
+    };
+
+    let results: Vec<Vec<String>> = if profile_alloc {
+        days.iter().map(run_day).collect()
+    } else {
+        parallel::par_map(&days, run_day)
+    };
+
+    for line in results.iter().flatten() {
+        println!("{line}");
+    }
+}
+
+fn perf_record(data: &Path, runs: usize, log: &Path) -> Result<(), String> {
+    let commit = perf::git_commit();
+    let mut records = Vec::new();
+
+    // Days run one at a time so they do not compete for cores
+    for (challenge, day) in challenges().iter().zip(FIRST_DAY..) {
+        let path = fetch::input_path(data, day);
+        if !path.exists() {
+            eprintln!("day {day}: missing {}", path.display());
+            continue;
+        }
+
+        for (part, mode) in [(1, Mode::Part1), (2, Mode::Part2)] {
+            match perf::time_runs(runs, || run_part(challenge.as_ref(), mode, &path)) {
+                Ok(timing) => {
+                    println!(
+                        "day {day} part{part}: median {}us over {} runs",
+                        timing.median, timing.runs
+                    );
+                    records.push(perf::Record::new(&commit, day, part, timing));
+                }
+                Err(e) => eprintln!("day {day} part{part}: error: {e}"),
+            }
+        }
+    }
+
+    perf::append(log, &records).map_err(|e| format!("{}: {e}", log.display()))
+}
+
+fn write_report(
+    data: &Path,
+    markdown: &Path,
+    html: &Path,
+    day10_engine: day10::AreaEngine,
+) -> Result<(), String> {
+    // Days run one at a time so the timings are comparable
+    let reports: Vec<report::DayReport> = challenges_with(day10_engine)
+        .iter()
+        .zip(FIRST_DAY..)
+        .map(|(challenge, day)| report::run_day(day, challenge.as_ref(), data))
+        .collect();
+
+    for (path, contents) in [
+        (markdown, report::markdown(&reports)),
+        (html, report::html(&reports)),
+    ] {
+        fs::write(path, contents).map_err(|e| format!("{}: {e}", path.display()))?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+/// Returns whether any part regressed.
+fn perf_compare(
+    baseline: &str,
+    current: Option<String>,
+    threshold: f64,
+    log: &Path,
+) -> Result<bool, String> {
+    let records = perf::read_log(log)?;
+    let current = match current {
+        Some(commit) => commit,
+        None => records
+            .iter()
+            .max_by_key(|r| r.time)
+            .map(|r| r.commit.clone())
+            .ok_or("Performance log is empty")?,
     };
-    println!("{result}");
+
+    let comparisons = perf::compare(&records, baseline, &current, threshold)?;
+    for c in &comparisons {
+        println!(
+            "day {} part{}: {}us -> {}us ({:+.1}%){}",
+            c.day,
+            c.part,
+            c.baseline,
+            c.current,
+            c.change(),
+            if c.regressed { " REGRESSION" } else { "" }
+        );
+    }
+    Ok(comparisons.iter().any(|c| c.regressed))
+}
+
+fn explain_hands(
+    file: &Path,
+    rules: &day7::Rules,
+    order: HandOrder,
+    filter: Option<&str>,
+) -> Result<(), String> {
+    let category = filter
+        .map(|name| {
+            rules
+                .category_index(name)
+                .ok_or(format!("Unknown hand type {name}"))
+        })
+        .transpose()?;
+
+    let mut explanations = day7::explain(file, rules)?;
+    let total: u128 = explanations.iter().map(|e| e.winnings).sum();
+    explanations.retain(|e| category.is_none_or(|c| e.category == c));
+    match order {
+        HandOrder::Rank => {}
+        // Stable, so hands of a type stay in rank order
+        HandOrder::Type => explanations.sort_by_key(|e| std::cmp::Reverse(e.category)),
+        HandOrder::Bid => explanations.sort_by_key(|e| std::cmp::Reverse(e.bid)),
+        HandOrder::Winnings => explanations.sort_by_key(|e| std::cmp::Reverse(e.winnings)),
+    }
+
+    println!(
+        "{:>6}  {:<8} {:<8} {:<16} {:>6} {:>10}",
+        "Rank", "Cards", "Played", "Type", "Bid", "Winnings"
+    );
+    for e in &explanations {
+        println!(
+            "{:>6}  {:<8} {:<8} {:<16} {:>6} {:>10}",
+            e.rank, e.cards, e.played_as, e.category_name, e.bid, e.winnings
+        );
+    }
+    println!("Total winnings: {total}");
+    Ok(())
+}
+
+fn main() {
+    let cli = Cli::parse();
+    parallel::set_jobs(cli.jobs);
+    if cli.profile_alloc {
+        allocs::enable();
+    }
+
+    match (cli.command, cli.solve) {
+        (Some(Command::All { data, day10_engine }), _) => {
+            run_all(&data, day10_engine, cli.profile_alloc)
+        }
+        (
+            Some(Command::Hands {
+                file,
+                preset,
+                rules,
+                explain,
+                sort,
+                filter,
+            }),
+            _,
+        ) => {
+            let rules = match (rules, preset) {
+                (Some(path), _) => day7::Rules::from_file(&path),
+                (None, Mode::Part1) => Ok(day7::Rules::part1()),
+                (None, Mode::Part2) => Ok(day7::Rules::part2()),
+            };
+            let result = rules.and_then(|rules| match explain {
+                true => explain_hands(&file, &rules, sort, filter.as_deref()),
+                false => day7::total_winnings(&file, &rules).map(|w| println!("{w}")),
+            });
+            if let Err(e) = result {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        (
+            Some(Command::Network {
+                file,
+                render: Render::Dot,
+                from,
+                to,
+                start,
+                steps,
+            }),
+            _,
+        ) => match day8::render_dot(&file, &from, &to, start.as_deref(), steps) {
+            Ok(dot) => print!("{dot}"),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        (Some(Command::Walk { file, from, to }), _) => match day8::steps(&file, &from, &to) {
+            Ok((steps, shortcut)) => {
+                if shortcut != Some(steps) {
+                    eprintln!(
+                        "Warning: the LCM of the first steps to a goal would give {shortcut:?}, not {steps}"
+                    );
+                }
+                println!("{steps}");
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        (Some(Command::Query { action }), _) => {
+            let result = match action {
+                QueryCommand::Steps {
+                    file,
+                    from,
+                    to,
+                    phase,
+                } => day8::shortest(&file, &from, phase, &to).map(|found| match found {
+                    Some((goal, steps)) => println!("{steps} steps to {goal}"),
+                    None => println!("No goal is reachable from {from}"),
+                }),
+                QueryCommand::Reachable { file, from, phase } => {
+                    day8::reachable(&file, &from, phase)
+                        .map(|nodes| println!("{}", nodes.join(" ")))
+                }
+                QueryCommand::Stuck { file, to } => {
+                    day8::never_reaching(&file, &to).map(|nodes| println!("{}", nodes.join(" ")))
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        (Some(Command::Serve { port }), _) => {
+            if let Err(e) = serve::serve(port) {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        (
+            Some(Command::Fetch {
+                day,
+                data,
+                base_url,
+                year,
+                min_interval,
+            }),
+            _,
+        ) => {
+            let interval = Duration::from_secs(min_interval);
+            match fetch::FetchConfig::from_env(base_url, year, interval)
+                .and_then(|config| fetch::fetch_input(&config, day, &data))
+            {
+                Ok(path) => println!("{}", path.display()),
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        (Some(Command::Perf { action }), _) => {
+            let result = match action {
+                PerfCommand::Record { data, runs, log } => perf_record(&data, runs.max(1), &log),
+                PerfCommand::Compare {
+                    baseline,
+                    current,
+                    threshold,
+                    log,
+                } => perf_compare(&baseline, current, threshold, &log).map(|regressed| {
+                    if regressed {
+                        std::process::exit(1);
+                    }
+                }),
+            };
+            if let Err(e) = result {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        (
+            Some(Command::Report {
+                data,
+                markdown,
+                html,
+                day10_engine,
+            }),
+            _,
+        ) => {
+            if let Err(e) = write_report(&data, &markdown, &html, day10_engine) {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        (
+            Some(Command::Samples {
+                page,
+                day,
+                data,
+                manifest,
+            }),
+            _,
+        ) => match samples::extract(&page, day, &data, &manifest) {
+            Ok(entries) => {
+                for e in entries {
+                    println!(
+                        "day {} part{}: {} = {}",
+                        e.day, e.part, e.sample, e.expected
+                    );
+                }
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        },
+        (None, Some(args)) => {
+            let challenges = challenges_with(args.day10_engine);
+            let path = Path::new(&args.file);
+            let index = (args.day as usize) - (FIRST_DAY as usize);
+            let challenge = &challenges[index];
+            let (result, stats) = allocs::measure(|| match args.mode {
+                Mode::Part1 => challenge.part1(path),
+                Mode::Part2 => challenge.part2(path),
+            });
+            println!("{result}");
+            if cli.profile_alloc {
+                eprintln!("{stats}");
+            }
+        }
+        (None, None) => {
+            use clap::CommandFactory;
+            Cli::command().print_help().unwrap();
+        }
+    }
 }
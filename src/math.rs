@@ -0,0 +1,215 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::{Div, Rem};
+
+#[derive(Debug, PartialEq)]
+pub enum MathError {
+    Overflow,
+    NoSolution,
+    InvalidModulus,
+}
+
+impl Error for MathError {}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use MathError as E;
+        match self {
+            E::Overflow => write!(f, "Arithmetic overflow"),
+            E::NoSolution => write!(f, "Congruences have no common solution"),
+            E::InvalidModulus => write!(f, "Modulus must be positive"),
+        }
+    }
+}
+
+pub trait Unsigned: Copy + PartialEq + Rem<Output = Self> + Div<Output = Self> {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {
+        $(impl Unsigned for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_mul(self, rhs)
+            }
+        })*
+    };
+}
+
+impl_unsigned!(u64, u128);
+
+pub fn gcd<T: Unsigned>(mut a: T, mut b: T) -> T {
+    while b != T::ZERO {
+        let old_b = b;
+        b = a % b;
+        a = old_b;
+    }
+    a
+}
+
+/// Least common multiple, or `None` if it does not fit in `T`.
+pub fn lcm<T: Unsigned>(a: T, b: T) -> Option<T> {
+    if a == T::ZERO || b == T::ZERO {
+        return Some(T::ZERO);
+    }
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+pub fn lcm_all<T: Unsigned>(numbers: &[T]) -> Option<T> {
+    numbers.iter().try_fold(T::ONE, |acc, &n| lcm(acc, n))
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g == gcd(a, b)`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+#[cfg(test)]
+pub fn mod_inverse(a: i128, modulus: i128) -> Option<i128> {
+    let (g, x, _) = extended_gcd(a.rem_euclid(modulus), modulus);
+    (g == 1).then(|| x.rem_euclid(modulus))
+}
+
+/// `x ≡ residue (mod modulus)`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Congruence {
+    pub residue: i128,
+    pub modulus: i128,
+}
+
+impl Congruence {
+    pub fn new(residue: i128, modulus: i128) -> Result<Self, MathError> {
+        if modulus <= 0 {
+            return Err(MathError::InvalidModulus);
+        }
+        Ok(Self {
+            residue: residue.rem_euclid(modulus),
+            modulus,
+        })
+    }
+
+    /// Combine two congruences into one, allowing moduli with common factors.
+    fn merge(self, other: Self) -> Result<Self, MathError> {
+        // The fields are public, so `other` need not have come from `new`
+        if other.modulus <= 0 {
+            return Err(MathError::InvalidModulus);
+        }
+        let (g, p, _) = extended_gcd(self.modulus, other.modulus);
+        let diff = other.residue - self.residue;
+        if diff % g != 0 {
+            return Err(MathError::NoSolution);
+        }
+
+        let other_reduced = other.modulus / g;
+        let modulus = (self.modulus / g)
+            .checked_mul(other.modulus)
+            .ok_or(MathError::Overflow)?;
+
+        // p is the inverse of self.modulus / g modulo other.modulus / g
+        let k = (diff / g).rem_euclid(other_reduced);
+        let k = k
+            .checked_mul(p.rem_euclid(other_reduced))
+            .ok_or(MathError::Overflow)?
+            % other_reduced;
+        let residue = self
+            .modulus
+            .checked_mul(k)
+            .and_then(|m| m.checked_add(self.residue))
+            .ok_or(MathError::Overflow)?;
+
+        Self::new(residue, modulus)
+    }
+}
+
+/// Chinese Remainder Theorem for moduli which need not be coprime.
+pub fn crt(congruences: &[Congruence]) -> Result<Congruence, MathError> {
+    congruences
+        .iter()
+        .try_fold(Congruence::new(0, 1)?, |acc, &c| acc.merge(c))
+}
+
+/// First time at which periodic events all happen together. Each event is
+/// `(start, period)` and happens at `start`, `start + period`, ...
+#[cfg(test)]
+pub fn align(events: &[(u64, u64)]) -> Result<u64, MathError> {
+    let congruences = events
+        .iter()
+        .map(|&(start, period)| Congruence::new(start as i128, period as i128))
+        .collect::<Result<Vec<Congruence>, MathError>>()?;
+    let Congruence { residue, modulus } = crt(&congruences)?;
+
+    // The CRT solution may come before some events have started
+    let earliest = events.iter().map(|&(start, _)| start).max().unwrap_or(0) as i128;
+    let periods = ((earliest - residue).max(0) as u128).div_ceil(modulus as u128) as i128;
+    let time = residue + periods * modulus;
+
+    u64::try_from(time).map_err(|_| MathError::Overflow)
+}
+
+#[test]
+fn test_gcd_lcm() {
+    assert_eq!(gcd(12u64, 18), 6);
+    assert_eq!(gcd(0u64, 5), 5);
+    assert_eq!(lcm(4u64, 6), Some(12));
+    assert_eq!(lcm_all(&[2u64, 3, 4]), Some(12));
+    assert_eq!(lcm(u64::MAX, u64::MAX - 1), None);
+    assert_eq!(
+        lcm(u64::MAX as u128, (u64::MAX - 1) as u128),
+        Some(u64::MAX as u128 * (u64::MAX - 1) as u128)
+    );
+}
+
+#[test]
+fn test_extended_gcd() {
+    let (g, x, y) = extended_gcd(240, 46);
+    assert_eq!(g, 2);
+    assert_eq!(240 * x + 46 * y, 2);
+    assert_eq!(mod_inverse(3, 11), Some(4));
+    assert_eq!(mod_inverse(4, 8), None);
+}
+
+#[test]
+fn test_crt() {
+    let c = |r, m| Congruence::new(r, m).unwrap();
+    assert_eq!(crt(&[c(2, 3), c(3, 5), c(2, 7)]), Ok(c(23, 105)));
+    assert_eq!(crt(&[c(3, 4), c(5, 6)]), Ok(c(11, 12)));
+    assert_eq!(crt(&[c(1, 4), c(2, 6)]), Err(MathError::NoSolution));
+
+    assert_eq!(Congruence::new(1, 0), Err(MathError::InvalidModulus));
+    assert_eq!(Congruence::new(1, -3), Err(MathError::InvalidModulus));
+    let zero = Congruence {
+        residue: 0,
+        modulus: 0,
+    };
+    assert_eq!(crt(&[c(1, 4), zero]), Err(MathError::InvalidModulus));
+}
+
+#[test]
+fn test_align() {
+    assert_eq!(align(&[(2, 3), (3, 5)]), Ok(8));
+    // 8 is a solution but the second event has not started yet
+    assert_eq!(align(&[(2, 3), (23, 5)]), Ok(23));
+    assert_eq!(align(&[(0, 4), (2, 4)]), Err(MathError::NoSolution));
+    assert_eq!(align(&[(0, 4), (2, 0)]), Err(MathError::InvalidModulus));
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub peak_entries: usize,
+    /// Estimated from the inline size of keys and values, so any heap data
+    /// they own is not counted.
+    pub peak_bytes: usize,
+}
+
+/// A cache for memoized recursion.
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    stats: CacheStats,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Memo<K, V> {
+    pub fn new() -> Self {
+        Self {
+            cache: HashMap::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Look up `key`, or compute it with `f` which may recurse back into
+    /// this cache. `ctx` carries whatever the computation needs besides the
+    /// key, so keys can stay small.
+    pub fn get_or_compute<C: ?Sized>(
+        &mut self,
+        ctx: &C,
+        key: K,
+        f: fn(&mut Self, &C, &K) -> V,
+    ) -> V {
+        if let Some(value) = self.cache.get(&key) {
+            self.stats.hits += 1;
+            return value.clone();
+        }
+        self.stats.misses += 1;
+
+        let value = f(self, ctx, &key);
+        self.insert(key, value.clone());
+        value
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.cache.insert(key, value);
+
+        let entries = self.cache.len();
+        self.stats.entries = entries;
+        if entries > self.stats.peak_entries {
+            self.stats.peak_entries = entries;
+            self.stats.peak_bytes = entries * (mem::size_of::<K>() + mem::size_of::<V>());
+        }
+    }
+
+    #[cfg(test)]
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+}
+
+#[test]
+fn test_memo() {
+    fn fibonacci(memo: &mut Memo<u64, u64>, _: &(), &n: &u64) -> u64 {
+        match n {
+            0 | 1 => n,
+            _ => {
+                memo.get_or_compute(&(), n - 1, fibonacci)
+                    + memo.get_or_compute(&(), n - 2, fibonacci)
+            }
+        }
+    }
+
+    let mut memo = Memo::new();
+    assert_eq!(memo.get_or_compute(&(), 90, fibonacci), 2880067194370816120);
+
+    let stats = memo.stats();
+    assert_eq!(stats.misses, 91);
+    assert_eq!(stats.hits, 88);
+    assert_eq!(stats.entries, 91);
+    assert_eq!(stats.peak_bytes, 91 * 16);
+}
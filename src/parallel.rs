@@ -0,0 +1,106 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+static JOBS: AtomicUsize = AtomicUsize::new(1);
+
+thread_local! {
+    static IN_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn set_jobs(jobs: usize) {
+    JOBS.store(jobs.max(1), Ordering::Relaxed);
+}
+
+pub fn jobs() -> usize {
+    JOBS.load(Ordering::Relaxed)
+}
+
+/// Map `f` over `items` using up to `jobs()` threads.
+///
+/// Results are returned in the same order as `items`.
+pub fn par_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    par_map_with(items, || (), |_, item| f(item))
+}
+
+/// Like `par_map`, but each thread gets its own state created by `init`,
+/// e.g. a cache which would otherwise need to be shared.
+///
+/// Calls made from inside a worker run sequentially so nested parallel
+/// sections do not multiply the thread count.
+pub fn par_map_with<T, S, R, I, F>(items: &[T], init: I, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    I: Fn() -> S + Sync,
+    F: Fn(&mut S, &T) -> R + Sync,
+{
+    par_map_with_jobs(jobs(), items, init, f)
+}
+
+/// Like `par_map_with`, but with an explicit number of threads rather
+/// than the global setting.
+pub fn par_map_with_jobs<T, S, R, I, F>(jobs: usize, items: &[T], init: I, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    I: Fn() -> S + Sync,
+    F: Fn(&mut S, &T) -> R + Sync,
+{
+    let jobs = jobs.min(items.len());
+    if jobs <= 1 || IN_WORKER.get() {
+        let mut state = init();
+        return items.iter().map(|item| f(&mut state, item)).collect();
+    }
+
+    let chunk_size = items.len().div_ceil(jobs);
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let (init, f) = (&init, &f);
+                scope.spawn(move || {
+                    IN_WORKER.set(true);
+                    let mut state = init();
+                    chunk
+                        .iter()
+                        .map(|item| f(&mut state, item))
+                        .collect::<Vec<R>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    })
+}
+
+#[test]
+fn test_par_map_order() {
+    let items: Vec<u32> = (0..100).collect();
+    let doubled = par_map_with_jobs(4, &items, || (), |_, &x| x * 2);
+    assert_eq!(doubled, (0..100).map(|x| x * 2).collect::<Vec<u32>>());
+}
+
+#[test]
+fn test_par_map_with_state() {
+    let items: Vec<u32> = (1..=10).collect();
+    let seen = par_map_with_jobs(
+        3,
+        &items,
+        || 0,
+        |count, _| {
+            *count += 1;
+            *count
+        },
+    );
+    // Each thread counts from 1 over its own chunk of 4, 4 and 2 items
+    assert_eq!(seen, vec![1, 2, 3, 4, 1, 2, 3, 4, 1, 2]);
+}
@@ -0,0 +1,352 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A parse failure at a 1-based line and column of the input.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+/// Reads through a single line of input, keeping track of the position so
+/// errors can point at it.
+#[derive(Clone, Copy)]
+pub struct Cursor<'a> {
+    line: usize,
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(line: usize, text: &'a str) -> Self {
+        Self { line, text, pos: 0 }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.text[..self.pos].chars().count() + 1
+    }
+
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.line, self.column(), message)
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    pub fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    /// Consume `literal` if the input continues with it.
+    pub fn eat(&mut self, literal: &str) -> bool {
+        if self.rest().starts_with(literal) {
+            self.pos += literal.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expect(&mut self, literal: &str) -> Result<(), ParseError> {
+        if self.eat(literal) {
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected '{literal}'")))
+        }
+    }
+
+    /// A non-empty run of anything but whitespace and the `=`, `,`, `(` and
+    /// `)` used to separate fields.
+    pub fn identifier(&mut self) -> Result<&'a str, ParseError> {
+        match self.take_while(|c| !c.is_whitespace() && !"=,()".contains(c)) {
+            "" => Err(self.error("Expected an identifier")),
+            identifier => Ok(identifier),
+        }
+    }
+
+    /// A non-empty run of letters, digits and underscores.
+    pub fn label(&mut self) -> Result<&'a str, ParseError> {
+        match self.take_while(|c| c.is_alphanumeric() || c == '_') {
+            "" => Err(self.error("Expected a label")),
+            label => Ok(label),
+        }
+    }
+
+    pub fn number<T>(&mut self) -> Result<T, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let start = *self;
+        let sign = if self.eat("-") { 1 } else { 0 };
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            *self = start;
+            return Err(self.error("Expected a number"));
+        }
+
+        let text = &start.rest()[..sign + digits.len()];
+        text.parse()
+            .map_err(|e| start.error(format!("Invalid number '{text}': {e}")))
+    }
+
+    /// Whitespace separated numbers up to the end of the line.
+    pub fn numbers<T>(&mut self) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let mut numbers = Vec::new();
+        self.skip_whitespace();
+        while !self.is_at_end() {
+            numbers.push(self.number()?);
+            self.skip_whitespace();
+        }
+        Ok(numbers)
+    }
+
+    /// One or more items separated by `separator`.
+    pub fn separated<T>(
+        &mut self,
+        separator: &str,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut items = vec![item(self)?];
+        while self.eat(separator) {
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    /// Convert each character up to the next whitespace, reporting the
+    /// column of the first one `cell` rejects.
+    pub fn cells<T, E: fmt::Display>(
+        &mut self,
+        cell: impl Fn(char) -> Result<T, E>,
+    ) -> Result<Vec<T>, ParseError> {
+        let mut cells = Vec::new();
+        while let Some(c) = self.peek().filter(|c| !c.is_whitespace()) {
+            cells.push(cell(c).map_err(|e| self.error(e.to_string()))?);
+            self.pos += c.len_utf8();
+        }
+        Ok(cells)
+    }
+
+    pub fn finish(&self) -> Result<(), ParseError> {
+        if self.is_at_end() {
+            Ok(())
+        } else {
+            Err(self.error(format!("Unexpected trailing input '{}'", self.rest())))
+        }
+    }
+}
+
+/// A `key = (left, right)` record.
+pub fn key_pair<'a>(cursor: &mut Cursor<'a>) -> Result<(&'a str, &'a str, &'a str), ParseError> {
+    let key = cursor.identifier()?;
+    cursor.expect(" = (")?;
+    let left = cursor.identifier()?;
+    cursor.expect(", ")?;
+    let right = cursor.identifier()?;
+    cursor.expect(")")?;
+    Ok((key, left, right))
+}
+
+/// A run of lines along with the line number it starts at.
+#[derive(Debug, Clone, Copy)]
+pub struct Block<'a> {
+    pub line: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Block<'a> {
+    /// The whole of `text`, less any blank lines at either end.
+    pub fn new(text: &'a str) -> Self {
+        let mut line = 1;
+        let mut text = text;
+        while let Some((first, rest)) = text.split_once('\n') {
+            if !first.trim().is_empty() {
+                break;
+            }
+            text = rest;
+            line += 1;
+        }
+        Self {
+            line,
+            text: text.trim_end(),
+        }
+    }
+
+    /// Parse every line with `f`, which must consume the whole line.
+    pub fn lines<T>(
+        &self,
+        mut f: impl FnMut(&mut Cursor<'a>) -> Result<T, ParseError>,
+    ) -> Result<Vec<T>, ParseError> {
+        self.text
+            .lines()
+            .zip(self.line..)
+            .map(|(text, line)| {
+                let mut cursor = Cursor::new(line, text);
+                let value = f(&mut cursor)?;
+                cursor.finish()?;
+                Ok(value)
+            })
+            .collect()
+    }
+
+    /// A rectangular grid with one cell per character.
+    pub fn grid<T, E: fmt::Display>(
+        &self,
+        cell: impl Fn(char) -> Result<T, E>,
+    ) -> Result<Vec<Vec<T>>, ParseError> {
+        let rows = self.lines(|cursor| cursor.cells(&cell))?;
+        if let Some(width) = rows.first().map(Vec::len) {
+            if let Some(row) = rows.iter().position(|r| r.len() != width) {
+                let column = rows[row].len().min(width) + 1;
+                return Err(ParseError::new(
+                    self.line + row,
+                    column,
+                    format!("Row has {} cells, expected {width}", rows[row].len()),
+                ));
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// Split `text` into blocks separated by blank lines.
+pub fn blocks(text: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+    let mut offset = 0;
+
+    for (raw, line) in text.split_inclusive('\n').zip(1..) {
+        match (raw.trim().is_empty(), start) {
+            (true, Some((line, from))) => {
+                blocks.push(Block {
+                    line,
+                    text: text[from..offset].trim_end(),
+                });
+                start = None;
+            }
+            (false, None) => start = Some((line, offset)),
+            _ => {}
+        }
+        offset += raw.len();
+    }
+    if let Some((line, from)) = start {
+        blocks.push(Block {
+            line,
+            text: text[from..].trim_end(),
+        });
+    }
+
+    blocks
+}
+
+/// Parse every line of `text` with `f`.
+pub fn lines<'a, T>(
+    text: &'a str,
+    f: impl FnMut(&mut Cursor<'a>) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    Block::new(text).lines(f)
+}
+
+pub fn grid<T, E: fmt::Display>(
+    text: &str,
+    cell: impl Fn(char) -> Result<T, E>,
+) -> Result<Vec<Vec<T>>, ParseError> {
+    Block::new(text).grid(cell)
+}
+
+#[test]
+fn test_cursor() {
+    let mut cursor = Cursor::new(3, "AAA = (BBB, CCC)");
+    assert_eq!(key_pair(&mut cursor), Ok(("AAA", "BBB", "CCC")));
+    assert!(cursor.is_at_end());
+
+    let mut cursor = Cursor::new(1, "start-1 = (a.b, ∆)");
+    assert_eq!(key_pair(&mut cursor), Ok(("start-1", "a.b", "∆")));
+
+    let mut cursor = Cursor::new(1, "AAA = BBB, CCC)");
+    assert_eq!(
+        key_pair(&mut cursor),
+        Err(ParseError::new(1, 4, "Expected ' = ('"))
+    );
+
+    let mut cursor = Cursor::new(1, "0 -3 12");
+    assert_eq!(cursor.numbers::<i32>(), Ok(vec![0, -3, 12]));
+
+    let mut cursor = Cursor::new(1, "1,2,x");
+    let err = cursor.separated(",", Cursor::number::<u8>).unwrap_err();
+    assert_eq!((err.line, err.column), (1, 5));
+
+    let mut cursor = Cursor::new(1, "12 300");
+    assert_eq!(cursor.number::<u8>(), Ok(12));
+    cursor.skip_whitespace();
+    assert_eq!(cursor.number::<u8>().unwrap_err().column, 4);
+}
+
+#[test]
+fn test_blocks() {
+    let text = "\nab\ncd\n\n\nef\n";
+    let blocks = blocks(text);
+    assert_eq!(blocks.len(), 2);
+    assert_eq!((blocks[0].line, blocks[0].text), (2, "ab\ncd"));
+    assert_eq!((blocks[1].line, blocks[1].text), (6, "ef"));
+
+    let to_digit = |c: char| c.to_digit(10).ok_or("Not a digit");
+    assert_eq!(grid("12\n34", to_digit), Ok(vec![vec![1, 2], vec![3, 4]]));
+    assert_eq!(
+        grid("\n12\n3x\n", to_digit),
+        Err(ParseError::new(3, 2, "Not a digit"))
+    );
+    assert_eq!(
+        grid("12\n3", to_digit),
+        Err(ParseError::new(2, 2, "Row has 1 cells, expected 2"))
+    );
+}
@@ -0,0 +1,302 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Timing statistics over several runs, in microseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    pub runs: usize,
+    pub min: u64,
+    pub median: u64,
+    pub mean: u64,
+    pub max: u64,
+}
+
+impl Timing {
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        let mut micros: Vec<u64> = samples.iter().map(|d| d.as_micros() as u64).collect();
+        micros.sort();
+
+        let runs = micros.len();
+        let median = match runs {
+            0 => return None,
+            n if n % 2 == 0 => (micros[n / 2 - 1] + micros[n / 2]) / 2,
+            n => micros[n / 2],
+        };
+
+        Some(Self {
+            runs,
+            min: micros[0],
+            median,
+            mean: micros.iter().sum::<u64>() / runs as u64,
+            max: micros[runs - 1],
+        })
+    }
+}
+
+/// Time `runs` calls of `f`, stopping at the first error.
+pub fn time_runs<T, E>(runs: usize, mut f: impl FnMut() -> Result<T, E>) -> Result<Timing, E> {
+    let mut samples = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let start = Instant::now();
+        f()?;
+        samples.push(start.elapsed());
+    }
+    Ok(Timing::from_samples(&samples).expect("At least one run"))
+}
+
+/// One line of the performance log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Seconds since the Unix epoch
+    pub time: u64,
+    pub commit: String,
+    pub day: u8,
+    pub part: u8,
+    pub timing: Timing,
+}
+
+impl Record {
+    pub fn new(commit: &str, day: u8, part: u8, timing: Timing) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self {
+            time,
+            commit: commit.to_string(),
+            day,
+            part,
+            timing,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        let t = &self.timing;
+        format!(
+            "{} {} {} {} {} {} {} {} {}",
+            self.time, self.commit, self.day, self.part, t.runs, t.min, t.median, t.mean, t.max
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [time, commit, day, part, runs, min, median, mean, max] = fields[..] else {
+            return None;
+        };
+        Some(Self {
+            time: time.parse().ok()?,
+            commit: commit.to_string(),
+            day: day.parse().ok()?,
+            part: part.parse().ok()?,
+            timing: Timing {
+                runs: runs.parse().ok()?,
+                min: min.parse().ok()?,
+                median: median.parse().ok()?,
+                mean: mean.parse().ok()?,
+                max: max.parse().ok()?,
+            },
+        })
+    }
+}
+
+/// The short hash of `HEAD`, marked `-dirty` if there are uncommitted
+/// changes since those could explain a difference.
+pub fn git_commit() -> String {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+    };
+
+    match git(&["rev-parse", "--short", "HEAD"]) {
+        Some(hash)
+            if git(&["status", "--porcelain", "--untracked-files=no"])
+                .is_some_and(|s| !s.is_empty()) =>
+        {
+            format!("{hash}-dirty")
+        }
+        Some(hash) => hash,
+        None => String::from("unknown"),
+    }
+}
+
+/// Records are only ever appended, so the log keeps the whole history.
+pub fn append(log: &Path, records: &[Record]) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(log)?;
+    for record in records {
+        writeln!(file, "{}", record.to_line())?;
+    }
+    Ok(())
+}
+
+pub fn read_log(log: &Path) -> Result<Vec<Record>, String> {
+    let contents = fs::read_to_string(log).map_err(|e| format!("{}: {e}", log.display()))?;
+    contents
+        .lines()
+        .zip(1..)
+        .filter(|(line, _)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(line, n)| {
+            Record::from_line(line).ok_or(format!("{}:{n}: invalid record", log.display()))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Comparison {
+    pub day: u8,
+    pub part: u8,
+    pub baseline: u64,
+    pub current: u64,
+    pub regressed: bool,
+}
+
+impl Comparison {
+    /// Percentage change in the median from the baseline.
+    pub fn change(&self) -> f64 {
+        (self.current as f64 - self.baseline as f64) / self.baseline.max(1) as f64 * 100.0
+    }
+}
+
+/// Hashes match by prefix so either a short or a full hash can be given,
+/// but results from a dirty tree only match other dirty ones.
+fn same_commit(a: &str, b: &str) -> bool {
+    let (a_hash, b_hash) = (a.trim_end_matches("-dirty"), b.trim_end_matches("-dirty"));
+    // Git never abbreviates below four characters
+    a_hash.len().min(b_hash.len()) >= 4
+        && (a_hash.len() < a.len()) == (b_hash.len() < b.len())
+        && (a_hash.starts_with(b_hash) || b_hash.starts_with(a_hash))
+}
+
+/// The newest record for each day and part at a commit, provided `commit`
+/// names exactly one of the recorded commits.
+fn latest_at<'a>(records: &'a [Record], commit: &str) -> Result<Vec<&'a Record>, String> {
+    let matching: Vec<&Record> = records
+        .iter()
+        .filter(|r| same_commit(&r.commit, commit))
+        .collect();
+    let mut commits: Vec<&str> = matching.iter().map(|r| r.commit.as_str()).collect();
+    commits.sort();
+    commits.dedup();
+    if commits.len() > 1 {
+        return Err(format!(
+            "Commit {commit} is ambiguous: {}",
+            commits.join(", ")
+        ));
+    }
+
+    let mut latest: Vec<&Record> = Vec::new();
+    for record in matching {
+        match latest
+            .iter_mut()
+            .find(|r| (r.day, r.part) == (record.day, record.part))
+        {
+            Some(existing) if existing.time <= record.time => *existing = record,
+            Some(_) => {}
+            None => latest.push(record),
+        }
+    }
+    latest.sort_by_key(|r| (r.day, r.part));
+    Ok(latest)
+}
+
+/// Compare medians for every day and part recorded at both commits,
+/// flagging those which slowed by more than `threshold` percent.
+pub fn compare(
+    records: &[Record],
+    baseline: &str,
+    current: &str,
+    threshold: f64,
+) -> Result<Vec<Comparison>, String> {
+    let before = latest_at(records, baseline)?;
+    if before.is_empty() {
+        return Err(format!("No records for baseline {baseline}"));
+    }
+    let after = latest_at(records, current)?;
+    if after.is_empty() {
+        return Err(format!("No records for {current}"));
+    }
+
+    let comparisons = after
+        .iter()
+        .filter_map(|a| {
+            let b = before.iter().find(|b| (b.day, b.part) == (a.day, a.part))?;
+            let mut comparison = Comparison {
+                day: a.day,
+                part: a.part,
+                baseline: b.timing.median,
+                current: a.timing.median,
+                regressed: false,
+            };
+            comparison.regressed = comparison.change() > threshold;
+            Some(comparison)
+        })
+        .collect();
+    Ok(comparisons)
+}
+
+#[test]
+fn test_timing() {
+    let ms = Duration::from_millis;
+    let timing = Timing::from_samples(&[ms(4), ms(1), ms(3), ms(2)]).unwrap();
+    assert_eq!(
+        timing,
+        Timing {
+            runs: 4,
+            min: 1000,
+            median: 2500,
+            mean: 2500,
+            max: 4000
+        }
+    );
+    assert_eq!(
+        Timing::from_samples(&[ms(5), ms(1), ms(2)]).unwrap().median,
+        2000
+    );
+    assert_eq!(Timing::from_samples(&[]), None);
+}
+
+#[test]
+fn test_compare() {
+    let record = |time, commit: &str, day, median| {
+        let timing = Timing {
+            runs: 1,
+            min: median,
+            median,
+            mean: median,
+            max: median,
+        };
+        Record {
+            time,
+            ..Record::new(commit, day, 1, timing)
+        }
+    };
+    let records = vec![
+        record(1, "aaaaaaa", 7, 100),
+        record(1, "aaaaaaa", 8, 100),
+        // A later run at the baseline commit replaces the earlier one
+        record(2, "aaaaaaa", 8, 200),
+        record(3, "bbbbbbb", 7, 111),
+        record(3, "bbbbbbb", 8, 210),
+        record(4, "bbbbbbb-dirty", 8, 500),
+        record(5, "aaaabbb", 7, 100),
+    ];
+
+    let line = records[0].to_line();
+    assert_eq!(Record::from_line(&line), Some(records[0].clone()));
+
+    let comparisons = compare(&records, "aaaaaaa1234", "bbbb", 10.0).unwrap();
+    assert_eq!(comparisons.len(), 2);
+    assert!(comparisons[0].regressed);
+    assert!(!comparisons[1].regressed);
+    assert!(compare(&records, "aaaaa", "bbbb-dirty", 10.0).unwrap()[0].regressed);
+    assert!(compare(&records, "cccc", "bbbb", 10.0).is_err());
+    // Too short to name a commit, or shared by two of them
+    assert!(compare(&records, "aaaaa", "bbb", 10.0).is_err());
+    assert!(compare(&records, "aaaa", "bbbb", 10.0).is_err());
+}
@@ -0,0 +1,97 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A live progress line on stderr for long-running solves.
+///
+/// Updates are cheap and thread-safe. Nothing is drawn unless stderr is a
+/// terminal, so piped output and test runs stay clean.
+pub struct Progress {
+    label: String,
+    total: Option<u64>,
+    current: AtomicU64,
+    enabled: bool,
+    last_draw: Mutex<Option<Instant>>,
+}
+
+impl Progress {
+    pub fn new(label: &str, total: Option<u64>) -> Self {
+        Self {
+            label: String::from(label),
+            total,
+            current: AtomicU64::new(0),
+            enabled: io::stderr().is_terminal(),
+            last_draw: Mutex::new(None),
+        }
+    }
+
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, n: u64) {
+        self.current.fetch_add(n, Ordering::Relaxed);
+        self.draw();
+    }
+
+    fn clear(&self) {
+        if self.enabled {
+            let mut stderr = io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+        }
+    }
+
+    fn render(&self) -> String {
+        let current = self.current.load(Ordering::Relaxed);
+        match self.total {
+            Some(total) if total > 0 => {
+                let percent = current * 100 / total;
+                format!("{}: {current}/{total} ({percent}%)", self.label)
+            }
+            _ => format!("{}: {current}", self.label),
+        }
+    }
+
+    fn draw(&self) {
+        if !self.enabled {
+            return;
+        }
+
+        let Ok(mut last_draw) = self.last_draw.try_lock() else {
+            // Another thread is drawing right now
+            return;
+        };
+
+        let now = Instant::now();
+        if last_draw.is_some_and(|t| now.duration_since(t) < REDRAW_INTERVAL) {
+            return;
+        }
+        *last_draw = Some(now);
+
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[2K{}", self.render());
+        let _ = stderr.flush();
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[test]
+fn test_render() {
+    let progress = Progress::new("Records", Some(8));
+    progress.add(2);
+    assert_eq!(progress.render(), "Records: 2/8 (25%)");
+
+    let progress = Progress::new("Iterations", None);
+    progress.add(41);
+    progress.inc();
+    assert_eq!(progress.render(), "Iterations: 42");
+}
@@ -0,0 +1,267 @@
+use crate::challenge::DailyChallenge;
+use crate::{catch_panic, fetch, run_part, Mode};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub struct PartReport {
+    pub part: u8,
+    pub answer: Result<u64, String>,
+    pub elapsed: Duration,
+}
+
+pub struct InputSize {
+    pub bytes: usize,
+    pub lines: usize,
+}
+
+pub struct DayReport {
+    pub day: u8,
+    pub title: &'static str,
+    pub description: &'static str,
+    /// `None` if the input file is missing
+    pub input: Option<InputSize>,
+    pub parts: Vec<PartReport>,
+    pub visualization: Option<String>,
+}
+
+/// Solve both parts of a day on `<data>/<day>.input`.
+pub fn run_day(day: u8, challenge: &dyn DailyChallenge, data: &Path) -> DayReport {
+    let mut report = DayReport {
+        day,
+        title: challenge.title(),
+        description: challenge.description(),
+        input: None,
+        parts: Vec::new(),
+        visualization: None,
+    };
+
+    let path = fetch::input_path(data, day);
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return report;
+    };
+    report.input = Some(InputSize {
+        bytes: contents.len(),
+        lines: contents.lines().count(),
+    });
+
+    for (part, mode) in [(1, Mode::Part1), (2, Mode::Part2)] {
+        let start = Instant::now();
+        let answer = run_part(challenge, mode, &path);
+        report.parts.push(PartReport {
+            part,
+            answer,
+            elapsed: start.elapsed(),
+        });
+    }
+    report.visualization = catch_panic(|| challenge.visualize(&path))
+        .unwrap_or_else(|e| Some(format!("Visualization failed: {e}")));
+
+    report
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    format!("{:.2} ms", elapsed.as_secs_f64() * 1000.0)
+}
+
+fn format_input(input: &Option<InputSize>) -> String {
+    match input {
+        Some(size) => format!("{} lines, {} bytes", size.lines, size.bytes),
+        None => String::from("missing"),
+    }
+}
+
+fn format_answer(answer: &Result<u64, String>) -> String {
+    match answer {
+        Ok(answer) => answer.to_string(),
+        Err(e) => format!("error: {e}"),
+    }
+}
+
+pub fn markdown(reports: &[DayReport]) -> String {
+    // Table cells cannot contain pipes or line breaks
+    let cell = |s: String| s.replace('|', "\\|").replace('\n', " ");
+
+    let mut md = String::from("# Advent of Code 2023\n\n");
+    md.push_str("| Day | Title | Input | Part 1 | Time | Part 2 | Time |\n");
+    md.push_str("| --- | --- | --- | --- | --- | --- | --- |\n");
+    for report in reports {
+        let mut row = vec![
+            report.day.to_string(),
+            report.title.to_string(),
+            format_input(&report.input),
+        ];
+        for part in &report.parts {
+            row.push(format_answer(&part.answer));
+            row.push(format_elapsed(part.elapsed));
+        }
+        row.resize(7, String::new());
+        let row: Vec<String> = row.into_iter().map(cell).collect();
+        md.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+
+    for report in reports {
+        md.push_str(&format!("\n## Day {}: {}\n\n", report.day, report.title));
+        md.push_str(&format!("{}\n\n", report.description));
+        md.push_str(&format!("- Input: {}\n", format_input(&report.input)));
+        for part in &report.parts {
+            md.push_str(&format!(
+                "- Part {}: `{}` in {}\n",
+                part.part,
+                format_answer(&part.answer),
+                format_elapsed(part.elapsed)
+            ));
+        }
+        if let Some(visualization) = &report.visualization {
+            md.push_str(&format!("\n```text\n{}\n```\n", visualization.trim_end()));
+        }
+    }
+
+    md
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: auto; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }
+.error { color: #b00; }
+pre { background: #f4f4f4; padding: 0.5em; overflow-x: auto; line-height: 1; }";
+
+/// A single page with the styles inlined, so it can be shared as one file.
+pub fn html(reports: &[DayReport]) -> String {
+    let answer_cell = |answer: &Result<u64, String>| match answer {
+        Ok(_) => format!("<td>{}</td>", escape_html(&format_answer(answer))),
+        Err(_) => format!(
+            "<td class=\"error\">{}</td>",
+            escape_html(&format_answer(answer))
+        ),
+    };
+
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Advent of Code 2023</title>\n");
+    html.push_str(&format!("<style>\n{STYLE}\n</style>\n</head>\n<body>\n"));
+    html.push_str("<h1>Advent of Code 2023</h1>\n<table>\n");
+    html.push_str("<tr><th>Day</th><th>Title</th><th>Input</th><th>Part 1</th><th>Time</th><th>Part 2</th><th>Time</th></tr>\n");
+    for report in reports {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td><a href=\"#day{}\">{}</a></td><td>{}</td>",
+            report.day,
+            report.day,
+            escape_html(report.title),
+            format_input(&report.input)
+        ));
+        for part in &report.parts {
+            html.push_str(&answer_cell(&part.answer));
+            html.push_str(&format!("<td>{}</td>", format_elapsed(part.elapsed)));
+        }
+        for _ in report.parts.len()..2 {
+            html.push_str("<td></td><td></td>");
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+
+    for report in reports {
+        html.push_str(&format!(
+            "<h2 id=\"day{}\">Day {}: {}</h2>\n<p>{}</p>\n",
+            report.day,
+            report.day,
+            escape_html(report.title),
+            escape_html(report.description)
+        ));
+        if let Some(visualization) = &report.visualization {
+            html.push_str(&format!("<pre>{}</pre>\n", escape_html(visualization)));
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[test]
+fn test_formats() {
+    let reports = vec![
+        DayReport {
+            day: 7,
+            title: "Camel Cards",
+            description: "Rank hands.",
+            input: Some(InputSize {
+                bytes: 50,
+                lines: 5,
+            }),
+            parts: vec![
+                PartReport {
+                    part: 1,
+                    answer: Ok(6440),
+                    elapsed: Duration::from_micros(1500),
+                },
+                PartReport {
+                    part: 2,
+                    answer: Err(String::from("a|b <c>")),
+                    elapsed: Duration::from_millis(2),
+                },
+            ],
+            visualization: Some(String::from("#.\n.#\n")),
+        },
+        DayReport {
+            day: 8,
+            title: "Haunted Wasteland",
+            description: "Walk the network.",
+            input: None,
+            parts: Vec::new(),
+            visualization: None,
+        },
+    ];
+
+    let md = markdown(&reports);
+    assert!(md.contains(
+        "| 7 | Camel Cards | 5 lines, 50 bytes | 6440 | 1.50 ms | error: a\\|b <c> | 2.00 ms |"
+    ));
+    assert!(md.contains("| 8 | Haunted Wasteland | missing |  |  |  |  |"));
+    assert!(md.contains("```text\n#.\n.#\n```"));
+
+    let html = html(&reports);
+    assert!(html.contains("<td class=\"error\">error: a|b &lt;c&gt;</td>"));
+    assert!(html.contains("<pre>#.\n.#\n</pre>"));
+    assert!(!html.contains("<c>"));
+}
+
+#[test]
+fn test_run_day_visualize_panic() {
+    struct Broken;
+    impl DailyChallenge for Broken {
+        fn part1(&self, _: &Path) -> u64 {
+            1
+        }
+        fn part2(&self, _: &Path) -> u64 {
+            2
+        }
+        fn title(&self) -> &'static str {
+            "Broken"
+        }
+        fn description(&self) -> &'static str {
+            "Panics while drawing."
+        }
+        fn visualize(&self, _: &Path) -> Option<String> {
+            panic!("no picture")
+        }
+    }
+
+    let data = std::env::temp_dir().join(format!("aoc-report-{}", std::process::id()));
+    fs::create_dir_all(&data).unwrap();
+    fs::write(fetch::input_path(&data, 7), "x\n").unwrap();
+    let report = run_day(7, &Broken, &data);
+    fs::remove_dir_all(&data).unwrap();
+
+    assert_eq!(report.parts.len(), 2);
+    assert_eq!(
+        report.visualization.as_deref(),
+        Some("Visualization failed: no picture")
+    );
+}
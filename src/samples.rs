@@ -0,0 +1,280 @@
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+pub const MANIFEST_FILE: &str = "samples.manifest";
+
+/// An example answer quoted on a puzzle page, tied to the example input it
+/// was computed from.
+#[derive(Debug, PartialEq)]
+struct ExampleAnswer {
+    part: u8,
+    sample: usize,
+    answer: u64,
+}
+
+#[derive(Debug, PartialEq)]
+struct PuzzlePage {
+    blocks: Vec<String>,
+    answers: Vec<ExampleAnswer>,
+}
+
+/// One line of the manifest: `<day> <part> <sample file> <expected answer>`
+#[derive(Debug, PartialEq, Clone)]
+pub struct ManifestEntry {
+    pub day: u8,
+    pub part: u8,
+    pub sample: String,
+    pub expected: u64,
+}
+
+fn unescape_html(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn sample_name(day: u8, index: usize) -> String {
+    match index {
+        0 => format!("{day}.sample"),
+        _ => format!("{day}.sample{}", index + 1),
+    }
+}
+
+/// Pull the `<pre><code>` blocks and the emphasised example answers out of a
+/// saved puzzle page. Each `<article>` is one part of the puzzle, and the last
+/// emphasised number after each code block in it is taken as the example
+/// answer for that block. Illustrations between an example and its answer can
+/// be mistaken for the example, so the manifest is worth a check afterwards.
+fn parse_page(html: &str) -> PuzzlePage {
+    let article_re = Regex::new(r"(?s)<article[^>]*>.*?</article>").unwrap();
+    let block_re = Regex::new(r"(?s)<pre><code>(.*?)</code></pre>").unwrap();
+    let answer_re =
+        Regex::new(r"<code><em>([^<]*)</em></code>|<em><code>([^<]*)</code></em>").unwrap();
+    let tag_re = Regex::new(r"<[^>]+>").unwrap();
+
+    let blocks: Vec<(usize, String)> = block_re
+        .captures_iter(html)
+        .map(|c| {
+            let whole = c.get(0).unwrap();
+            let text = unescape_html(&tag_re.replace_all(&c[1], ""));
+            (whole.start(), text)
+        })
+        .collect();
+
+    let mut answers: Vec<ExampleAnswer> = Vec::new();
+    for (part, article) in (1..).zip(article_re.find_iter(html)) {
+        for c in answer_re.captures_iter(article.as_str()) {
+            let position = article.start() + c.get(0).unwrap().start();
+            let text = c.get(1).or(c.get(2)).unwrap().as_str();
+            let Ok(answer) = text.parse::<u64>() else {
+                continue;
+            };
+            let Some(sample) = blocks.iter().rposition(|(start, _)| *start < position) else {
+                continue;
+            };
+            // A later answer for the same example supersedes intermediate values
+            match answers.last_mut() {
+                Some(last) if last.part == part && last.sample == sample => last.answer = answer,
+                _ => answers.push(ExampleAnswer {
+                    part,
+                    sample,
+                    answer,
+                }),
+            }
+        }
+    }
+
+    PuzzlePage {
+        blocks: blocks.into_iter().map(|(_, text)| text).collect(),
+        answers,
+    }
+}
+
+pub fn read_manifest(file: &Path) -> Result<Vec<ManifestEntry>, String> {
+    let contents = fs::read_to_string(file).map_err(|e| e.to_string())?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(n, line)| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [day, part, sample, expected] = fields[..] else {
+                return Err(format!("Line {}: expected 4 fields", n + 1));
+            };
+            let parse_err = |e: std::num::ParseIntError| format!("Line {}: {e}", n + 1);
+            Ok(ManifestEntry {
+                day: day.parse().map_err(parse_err)?,
+                part: part.parse().map_err(parse_err)?,
+                sample: String::from(sample),
+                expected: expected.parse().map_err(parse_err)?,
+            })
+        })
+        .collect()
+}
+
+fn write_manifest(file: &Path, entries: &[ManifestEntry]) -> Result<(), String> {
+    let mut contents = String::from("# day part sample expected\n");
+    for e in entries {
+        contents.push_str(&format!(
+            "{} {} {} {}\n",
+            e.day, e.part, e.sample, e.expected
+        ));
+    }
+    fs::write(file, contents).map_err(|e| e.to_string())
+}
+
+/// Write the example inputs referenced by a saved puzzle page into `data` and
+/// record their answers in `manifest`, replacing any previous entries for
+/// `day`. Existing sample files must already hold the same example.
+pub fn extract(
+    page: &Path,
+    day: u8,
+    data: &Path,
+    manifest: &Path,
+) -> Result<Vec<ManifestEntry>, String> {
+    let html = fs::read_to_string(page).map_err(|e| e.to_string())?;
+    let page = parse_page(&html);
+
+    let mut used: Vec<usize> = page.answers.iter().map(|a| a.sample).collect();
+    used.sort();
+    used.dedup();
+    if used.is_empty() && !page.blocks.is_empty() {
+        used.push(0);
+    }
+
+    fs::create_dir_all(data).map_err(|e| e.to_string())?;
+    for (index, &block) in used.iter().enumerate() {
+        let path = data.join(sample_name(day, index));
+        let text = &page.blocks[block];
+        match fs::read_to_string(&path) {
+            Ok(existing) if existing == *text => {}
+            // The manifest pairs answers with file names, so a file holding
+            // another example would be checked against the wrong answer
+            Ok(_) => {
+                return Err(format!(
+                    "{} differs from the page's example, remove it to re-extract",
+                    path.display()
+                ))
+            }
+            Err(_) => fs::write(&path, text).map_err(|e| e.to_string())?,
+        }
+    }
+
+    let new_entries: Vec<ManifestEntry> = page
+        .answers
+        .iter()
+        .map(|a| ManifestEntry {
+            day,
+            part: a.part,
+            sample: sample_name(day, used.iter().position(|&b| b == a.sample).unwrap()),
+            expected: a.answer,
+        })
+        .collect();
+
+    let mut entries = match manifest.exists() {
+        true => read_manifest(manifest)?,
+        false => Vec::new(),
+    };
+    entries.retain(|e| e.day != day);
+    entries.extend(new_entries.iter().cloned());
+    entries.sort_by_key(|e| (e.day, e.part, e.sample.clone()));
+    write_manifest(manifest, &entries)?;
+
+    Ok(new_entries)
+}
+
+#[test]
+fn test_parse_page() {
+    let html = r#"
+<article class="day-desc"><h2>--- Day 8 ---</h2>
+<p>For example:</p>
+<pre><code>LR
+
+AAA = (ZZZ, ZZZ)
+</code></pre>
+<p>This takes <code><em>2</em></code> steps.</p>
+<pre><code><em>L</em>L &amp; R
+</code></pre>
+<p>Here it takes <code><em>6</em></code> steps.</p>
+</article>
+<p>Your puzzle answer was <code>12345</code>.</p>
+<article class="day-desc"><h2>--- Part Two ---</h2>
+<p>It takes <code><em>6</em></code> steps again.</p>
+</article>"#;
+
+    let page = parse_page(html);
+    assert_eq!(page.blocks, vec!["LR\n\nAAA = (ZZZ, ZZZ)\n", "LL & R\n"]);
+    assert_eq!(
+        page.answers,
+        vec![
+            ExampleAnswer {
+                part: 1,
+                sample: 0,
+                answer: 2
+            },
+            ExampleAnswer {
+                part: 1,
+                sample: 1,
+                answer: 6
+            },
+            ExampleAnswer {
+                part: 2,
+                sample: 1,
+                answer: 6
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_extract() {
+    let dir = std::env::temp_dir().join(format!("aoc-samples-{}", std::process::id()));
+    let (page, data, manifest) = (dir.join("page.html"), dir.join("data"), dir.join("m"));
+    fs::create_dir_all(&dir).unwrap();
+    let html = "<article><pre><code>1 2\n</code></pre><p><code><em>3</em></code></p></article>";
+    fs::write(&page, html).unwrap();
+
+    let result = extract(&page, 9, &data, &manifest);
+    let again = extract(&page, 9, &data, &manifest);
+    fs::write(data.join("9.sample"), "4 5\n").unwrap();
+    let changed = extract(&page, 9, &data, &manifest);
+    let entries = read_manifest(&manifest);
+    fs::remove_dir_all(&dir).unwrap();
+
+    let expected = vec![ManifestEntry {
+        day: 9,
+        part: 1,
+        sample: String::from("9.sample"),
+        expected: 3,
+    }];
+    assert_eq!(result.as_ref(), Ok(&expected));
+    assert_eq!(again, result);
+    assert!(changed
+        .unwrap_err()
+        .contains("differs from the page's example"));
+    assert_eq!(entries, Ok(expected));
+}
+
+#[test]
+fn test_sample_manifest() {
+    use crate::{challenges, run_part, Mode, FIRST_DAY};
+
+    let data = Path::new("data");
+    let entries = read_manifest(Path::new(MANIFEST_FILE)).expect("Sample manifest missing");
+    assert!(!entries.is_empty(), "Sample manifest is empty");
+
+    let challenges = challenges();
+    for entry in entries {
+        let challenge = entry
+            .day
+            .checked_sub(FIRST_DAY)
+            .and_then(|i| challenges.get(i as usize))
+            .unwrap_or_else(|| panic!("No challenge for {entry:?}"));
+        let mode = Mode::from_number(entry.part).expect("Part should be 1 or 2");
+        let result = run_part(challenge.as_ref(), mode, &data.join(&entry.sample));
+        assert_eq!(result, Ok(entry.expected), "{entry:?}");
+    }
+}
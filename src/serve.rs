@@ -0,0 +1,220 @@
+use crate::{challenges, run_part, Mode, FIRST_DAY};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+static REQUEST_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Puzzle inputs are a few tens of kilobytes; anything far beyond that is refused
+const MAX_BODY: usize = 1 << 20;
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: String) -> Self {
+        Self { status, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, format!("{{\"error\":{}}}", json_string(message)))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn parse_part(part: &str) -> Option<Mode> {
+    let number = part.strip_prefix("part").unwrap_or(part);
+    number.parse().ok().and_then(Mode::from_number)
+}
+
+fn list_days() -> Response {
+    let days: Vec<String> = challenges()
+        .iter()
+        .zip(FIRST_DAY..)
+        .map(|(challenge, day)| {
+            let parameters: Vec<String> = challenge
+                .parameters()
+                .iter()
+                .map(|(name, value)| format!("{}:{}", json_string(name), json_string(value)))
+                .collect();
+            format!(
+                "{{\"day\":{day},\"parts\":[1,2],\"parameters\":{{{}}}}}",
+                parameters.join(",")
+            )
+        })
+        .collect();
+
+    Response::json(200, format!("[{}]", days.join(",")))
+}
+
+fn solve(day: &str, part: &str, input: &[u8]) -> Response {
+    let challenges = challenges();
+    let Some((day, challenge)) = day.parse::<u8>().ok().and_then(|d| {
+        let i = d.checked_sub(FIRST_DAY)?;
+        Some((d, challenges.get(i as usize)?))
+    }) else {
+        return Response::error(404, &format!("Unknown day {day}"));
+    };
+    let Some(mode) = parse_part(part) else {
+        return Response::error(404, &format!("Unknown part {part}"));
+    };
+
+    // Solvers read their input from a file, so stage the request body in one
+    let id = REQUEST_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("aoc-serve-{}-{id}.input", std::process::id()));
+    if let Err(e) = fs::write(&path, input) {
+        return Response::error(500, &e.to_string());
+    }
+
+    let start = Instant::now();
+    let result = run_part(challenge.as_ref(), mode, &path);
+    let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let _ = fs::remove_file(&path);
+
+    let (status, answer, error) = match result {
+        Ok(answer) => (200, answer.to_string(), String::from("null")),
+        Err(e) => (500, String::from("null"), json_string(&e)),
+    };
+    Response::json(
+        status,
+        format!(
+            "{{\"day\":{day},\"part\":{},\"answer\":{answer},\"elapsed_ms\":{elapsed_ms:.3},\"error\":{error}}}",
+            mode as u8 + 1
+        ),
+    )
+}
+
+fn route(method: &str, path: &str, body: &[u8]) -> Response {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, &segments[..]) {
+        ("GET", ["days"]) => list_days(),
+        ("POST", ["solve", day, part]) => solve(day, part, body),
+        (_, ["days"]) | (_, ["solve", _, _]) => Response::error(405, "Method not allowed"),
+        _ => Response::error(404, &format!("No route for {path}")),
+    }
+}
+
+fn handle_connection(stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return write_response(stream, Response::error(400, "Malformed request line"));
+    };
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    if content_length > MAX_BODY {
+        return write_response(stream, Response::error(413, "Request body too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    write_response(stream, route(method, path, &body))
+}
+
+fn write_response(mut stream: TcpStream, response: Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+pub fn serve(port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream) {
+                eprintln!("Connection error: {e}");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_json_string() {
+    assert_eq!(json_string("a \"b\"\n"), "\"a \\\"b\\\"\\n\"");
+}
+
+#[test]
+fn test_list_days() {
+    let response = route("GET", "/days", &[]);
+    assert_eq!(response.status, 200);
+    assert!(response.body.starts_with("[{\"day\":7,"));
+    assert!(response
+        .body
+        .contains("\"part2_spin_cycles\":\"1000000000\""));
+}
+
+#[test]
+fn test_solve() {
+    let input = b"0 3 6 9 12 15\n1 3 6 10 15 21\n10 13 16 21 30 45\n";
+    let response = route("POST", "/solve/9/1", input);
+    assert_eq!(response.status, 200);
+    assert!(response.body.contains("\"answer\":114,"));
+    assert!(response.body.contains("\"error\":null"));
+
+    let response = route("POST", "/solve/09/1", input);
+    assert!(response.body.starts_with("{\"day\":9,"));
+
+    assert_eq!(route("POST", "/solve/3/1", input).status, 404);
+    assert_eq!(route("GET", "/solve/9/1", input).status, 405);
+}